@@ -27,11 +27,19 @@ impl Runnable for DummyRobot {
         let directions = vec![Direction::Left, Direction::Right, Direction::Up, Direction::Down];
         let _ = go(self, world, directions.choose().unwrap().clone());
 
+        // Debug drawings are shown until the next game info, press B to list them
+        // self.channel.borrow_mut().draw_marker((10, 10), RED, "target");
+        // Custom values are shown in the telemetry window, press J to open it
+        // self.channel.borrow_mut().send_custom("state", "exploring");
+
         // You need to call this method to update the GUI
         self.channel.borrow_mut().send_game_info(self, world);
     }
 
     fn handle_event(&mut self, event: Event) {
+        // Needed to show the backpack changes and the energy consumption in the GUI
+        self.channel.borrow_mut().send_event(&event);
+
         match event {
             Event::Ready => {}
             Event::Terminated => {}
@@ -76,6 +84,8 @@ async fn main() {
     // Channel
     // This is a channel used by the robot to comunicate with the GUI 
     let channel = Rc::new(RefCell::new(Channel::default()));
+    // The updates can also be published on a local socket, run the stream_client example to see them
+    // channel.borrow_mut().enable_streaming("127.0.0.1:7420").expect("Could not start streaming");
 
     // World Generator
     let world_size = 200;
@@ -94,8 +104,24 @@ async fn main() {
     // Robot
     // Your robot must have channel as a field
     let robot = Box::new(DummyRobot::new(Rc::clone(&channel)));
+    // Wrap it to be able to drive it from the keyboard (press M to toggle manual control)
+    // let robot = Box::new(ManualControl::new(DummyRobot::new(Rc::clone(&channel)), Rc::clone(&channel)));
     
     // Visualizer
-    let mut visualizer = Visualizer::new(robot, world_generator, world_size, Rc::clone(&channel));
-    visualizer.start().await
+    let mut visualizer = Visualizer::new(robot, world_generator, Rc::clone(&channel));
+    // The default settings can be changed with the builder
+    // let mut visualizer = VisualizerBuilder::new()
+    //     .tick_time(0.1)
+    //     .start_paused(true)
+    //     .grab_mouse(false)
+    //     .camera_mode(CameraMode::FollowRobot)
+    //     .alerts(AlertSettings { pause_on_alert: true, ..Default::default() })
+    //     .build(robot, world_generator, Rc::clone(&channel));
+    // Other robots can be shown in the same view, each one needs its own channel and a generator producing the same world
+    // visualizer.add_robot("Teammate", RED, other_robot, other_world_generator, Rc::clone(&other_channel));
+    // Plugins can draw custom overlays and panels, see the OlympusPlugin trait
+    // visualizer.add_plugin(Box::new(MyPlugin::default()));
+    if let Err(error) = visualizer.start().await {
+        eprintln!("{}", error);
+    }
 }
\ No newline at end of file
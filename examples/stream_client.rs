@@ -0,0 +1,33 @@
+use std::io::{BufRead, BufReader};
+use std::net::TcpStream;
+
+use olympus::stream::StreamMessage;
+
+// Example client for the stream published by a channel
+// Start the visualizer with streaming enabled (see example.rs), then run:
+// cargo run --example stream_client -- 127.0.0.1:7420
+fn main() {
+    let address = std::env::args().nth(1).unwrap_or("127.0.0.1:7420".to_string());
+    let stream = TcpStream::connect(&address).expect("Could not connect to the visualizer");
+    println!("Connected to {}", address);
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+
+        match serde_json::from_str::<StreamMessage>(&line) {
+            Ok(StreamMessage::Snapshot { rows, cols, tiles, robot, weather, events }) => {
+                println!("Snapshot: {}x{} world, {} explored tiles, {} events", rows, cols, tiles.len(), events.len());
+                println!("Robot at ({}, {}) with {} energy, {} {}", robot.row, robot.col, robot.energy, weather.time_of_day, weather.weather_condition);
+            }
+            Ok(StreamMessage::MapDelta { tiles }) => println!("{} tiles discovered or changed", tiles.len()),
+            Ok(StreamMessage::Robot(robot)) => println!("Robot at ({}, {}), energy: {}, score: {}", robot.row, robot.col, robot.energy, robot.score),
+            Ok(StreamMessage::Weather(weather)) => println!("{} {} {}", weather.time_of_day, weather.day_time, weather.weather_condition),
+            Ok(StreamMessage::Event { text }) => println!("Event: {}", text),
+            Err(error) => println!("Invalid message: {}", error),
+        }
+    }
+
+    println!("Disconnected");
+}
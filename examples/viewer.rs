@@ -0,0 +1,32 @@
+use macroquad::prelude::*;
+use olympus::Visualizer;
+
+// Example of a viewer attached to a robot running in another process
+// Start the robot with streaming enabled (see example.rs), then run:
+// cargo run --example viewer -- 127.0.0.1:7420
+fn window_conf() -> Conf {
+    Conf {
+        window_title: "Olympus viewer".to_string(),
+        window_width: 1920,
+        window_height: 1080,
+        fullscreen: false,
+        ..Default::default()
+    }
+}
+
+#[macroquad::main(window_conf)]
+async fn main() {
+    let address = std::env::args().nth(1).unwrap_or("127.0.0.1:7420".to_string());
+
+    // The viewer waits for the first update of the robot, then renders the stream until the window is closed
+    let mut visualizer = match Visualizer::new_viewer(&address) {
+        Ok(visualizer) => visualizer,
+        Err(error) => {
+            eprintln!("Could not attach to the robot: {}", error);
+            return;
+        }
+    };
+    if let Err(error) = visualizer.start().await {
+        eprintln!("{}", error);
+    }
+}
@@ -0,0 +1,196 @@
+use std::{cell::RefCell, io::Read, net::TcpStream, path::PathBuf, rc::Rc};
+
+use macroquad::prelude::*;
+use robotics_lib::{runner::Runnable, world::world_generator::Generator};
+
+use crate::channel::Channel;
+use crate::gui::GUISettings;
+use crate::{AlertSettings, CameraMode, KeyboardControls, Visualizer};
+
+/// Configuration of a [`Visualizer`], every setting starts from the same default used by [`Visualizer::new`]
+///
+/// ```ignore
+/// let mut visualizer = VisualizerBuilder::new()
+///     .tick_time(0.1)
+///     .start_paused(true)
+///     .camera_mode(CameraMode::FollowRobot)
+///     .build(robot, world_generator, channel);
+/// ```
+pub struct VisualizerBuilder {
+    title: String,
+    tick_time: f32,
+    show_hud: bool,
+    daylight_cycle: bool,
+    grab_mouse: bool,
+    start_paused: bool,
+    camera_position: Vec3,
+    camera_mode: CameraMode,
+    keyboard_controls: KeyboardControls,
+    texture_pack: Option<PathBuf>,
+    hud_scale: f32,
+    hud_layout_file: Option<PathBuf>,
+    alerts: AlertSettings,
+}
+
+impl Default for VisualizerBuilder {
+    fn default() -> Self {
+        Self {
+            title: "Olympus".to_string(),
+            tick_time: 0.5,
+            show_hud: true,
+            daylight_cycle: true,
+            grab_mouse: true,
+            start_paused: false,
+            camera_position: vec3(-10.0, 100.0, -10.0),
+            camera_mode: CameraMode::Free,
+            keyboard_controls: Default::default(),
+            texture_pack: None,
+            hud_scale: 1.0,
+            hud_layout_file: None,
+            alerts: Default::default(),
+        }
+    }
+}
+
+impl VisualizerBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Macroquad creates the window before `main` runs, so the title is applied through the window configuration:
+    ///
+    /// ```ignore
+    /// fn window_conf() -> Conf {
+    ///     VisualizerBuilder::new().title("My robot").window_conf()
+    /// }
+    ///
+    /// #[macroquad::main(window_conf)]
+    /// async fn main() { ... }
+    /// ```
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Seconds between two game ticks
+    pub fn tick_time(mut self, tick_time: f32) -> Self {
+        self.tick_time = tick_time;
+        self
+    }
+
+    pub fn show_hud(mut self, show_hud: bool) -> Self {
+        self.show_hud = show_hud;
+        self
+    }
+
+    pub fn daylight_cycle(mut self, daylight_cycle: bool) -> Self {
+        self.daylight_cycle = daylight_cycle;
+        self
+    }
+
+    /// Whether the mouse is grabbed by the window at start, it can be released with the keyboard later
+    pub fn grab_mouse(mut self, grab_mouse: bool) -> Self {
+        self.grab_mouse = grab_mouse;
+        self
+    }
+
+    /// Starts without running game ticks, resume from the console
+    pub fn start_paused(mut self, start_paused: bool) -> Self {
+        self.start_paused = start_paused;
+        self
+    }
+
+    pub fn camera_position(mut self, camera_position: Vec3) -> Self {
+        self.camera_position = camera_position;
+        self
+    }
+
+    pub fn camera_mode(mut self, camera_mode: CameraMode) -> Self {
+        self.camera_mode = camera_mode;
+        self
+    }
+
+    pub fn keyboard_controls(mut self, keyboard_controls: KeyboardControls) -> Self {
+        self.keyboard_controls = keyboard_controls;
+        self
+    }
+
+    /// Folder with the same layout of the assets folder of this crate (e.g. `tiles/sand.png`),
+    /// the textures missing from it are replaced by the built-in ones
+    pub fn texture_pack(mut self, texture_pack: impl Into<PathBuf>) -> Self {
+        self.texture_pack = Some(texture_pack.into());
+        self
+    }
+
+    /// Size of the HUD windows and text, 1 is the default size, use 2 on 4K screens.
+    /// It can also be changed from the console with `hud scale <scale>`
+    pub fn hud_scale(mut self, hud_scale: f32) -> Self {
+        self.hud_scale = hud_scale;
+        self
+    }
+
+    /// File where the positions of the windows dragged with the mouse are saved and loaded from in the next runs.
+    /// Without it the windows start from their default position in every run
+    pub fn hud_layout_file(mut self, hud_layout_file: impl Into<PathBuf>) -> Self {
+        self.hud_layout_file = Some(hud_layout_file.into());
+        self
+    }
+
+    /// Events of the focused robot that show a notification and optionally pause the run
+    pub fn alerts(mut self, alerts: AlertSettings) -> Self {
+        self.alerts = alerts;
+        self
+    }
+
+    /// Window configuration to return from the function passed to `#[macroquad::main]`
+    pub fn window_conf(&self) -> Conf {
+        Conf {
+            window_title: self.title.clone(),
+            window_width: 1920,
+            window_height: 1080,
+            fullscreen: false,
+            ..Default::default()
+        }
+    }
+
+    fn visualizer(self) -> Visualizer {
+        let tick_time = Rc::new(RefCell::new(self.tick_time));
+        let settings = GUISettings {
+            show_hud: self.show_hud,
+            daylight_cycle: self.daylight_cycle,
+            start_paused: self.start_paused,
+            camera_position: self.camera_position,
+            camera_mode: self.camera_mode,
+            keyboard_controls: self.keyboard_controls,
+            texture_pack: self.texture_pack,
+            hud_scale: self.hud_scale,
+            hud_layout_file: self.hud_layout_file,
+            alerts: self.alerts,
+        };
+
+        Visualizer::empty(tick_time, settings, self.grab_mouse)
+    }
+
+    /// See [`Visualizer::new`]
+    pub fn build(self, robot: Box<dyn Runnable>, world_generator: impl Generator, channel: Rc<RefCell<Channel>>) -> Visualizer {
+        let mut visualizer = self.visualizer();
+        visualizer.add_robot("Robot", WHITE, robot, world_generator, channel);
+
+        visualizer
+    }
+
+    /// See [`Visualizer::new_viewer`]
+    pub fn build_viewer(self, address: &str) -> Result<Visualizer, String> {
+        let stream = TcpStream::connect(address).map_err(|error| format!("Error connecting to {}: {}", address, error))?;
+        self.build_viewer_from_reader(stream)
+    }
+
+    /// See [`Visualizer::new_viewer_from_reader`]
+    pub fn build_viewer_from_reader(self, reader: impl Read + Send + 'static) -> Result<Visualizer, String> {
+        let (channel, subscriber) = Visualizer::subscribe(reader)?;
+        let mut visualizer = self.visualizer();
+        visualizer.add_remote_robot(subscriber, channel);
+
+        Ok(visualizer)
+    }
+}
@@ -1,11 +1,26 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::Display;
+use std::net::SocketAddr;
 
+use macroquad::color::Color;
+use robotics_lib::energy::Energy;
+use robotics_lib::event::events::Event;
 use robotics_lib::interface::{get_score, robot_map};
 use robotics_lib::runner::Runnable;
 use robotics_lib::world::environmental_conditions::{DayTime, EnvironmentalConditions, WeatherType};
 use robotics_lib::world::tile::{Content, Tile};
 use robotics_lib::world::World;
 
+use serde::{Deserialize, Serialize};
+
+use crate::manual_control::ManualCommand;
+use crate::stream::{Publisher, RobotState, StreamMessage, StreamTile, WeatherState};
+use crate::world_info::{content_from_name, content_name, day_time_from_name, weather_from_name, weather_name};
+
+const EVENT_LOG_SIZE: usize = 100;
+const HISTORY_SIZE: usize = 10_000;
+const ROBOT_PATH_SIZE: usize = 10_000;
+
 #[allow(dead_code)]
 enum MessageType {
     GameUpdate,
@@ -18,17 +33,130 @@ struct Message {
     content: Vec<bool>,
 }
 
-pub(super) struct ChannelData {
-    pub explored_world_map: Vec<Vec<Option<Tile>>>,
-    pub robot_coordinates: (usize, usize),
+/// Value of a custom metric sent by the robot with [`Channel::send_custom`]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CustomValue {
+    Bool(bool),
+    Number(f64),
+    Text(String),
+}
+
+impl CustomValue {
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            CustomValue::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+}
+
+impl Display for CustomValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CustomValue::Bool(value) => write!(f, "{}", value),
+            CustomValue::Number(value) => write!(f, "{}", value),
+            CustomValue::Text(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<bool> for CustomValue {
+    fn from(value: bool) -> Self { CustomValue::Bool(value) }
+}
+impl From<f64> for CustomValue {
+    fn from(value: f64) -> Self { CustomValue::Number(value) }
+}
+impl From<f32> for CustomValue {
+    fn from(value: f32) -> Self { CustomValue::Number(value as f64) }
+}
+impl From<i32> for CustomValue {
+    fn from(value: i32) -> Self { CustomValue::Number(value as f64) }
+}
+impl From<i64> for CustomValue {
+    fn from(value: i64) -> Self { CustomValue::Number(value as f64) }
+}
+impl From<usize> for CustomValue {
+    fn from(value: usize) -> Self { CustomValue::Number(value as f64) }
+}
+impl From<&str> for CustomValue {
+    fn from(value: &str) -> Self { CustomValue::Text(value.to_string()) }
+}
+impl From<String> for CustomValue {
+    fn from(value: String) -> Self { CustomValue::Text(value) }
+}
+
+/// Metrics recorded every time the robot sends its game info
+#[derive(Clone, Serialize)]
+pub struct TickMetrics {
+    pub tick: usize,
+    pub time_of_day: String,
+    pub weather_condition: &'static str,
+    pub robot_row: usize,
+    pub robot_col: usize,
     pub robot_energy: usize,
-    pub robot_backpack_contents: HashMap<Content, usize>,
-    pub robot_backpack_size: usize,
-    pub discoverable_tiles: usize,
     pub robot_score: f32,
-    pub time_of_day: DayTime,
-    pub time_of_day_string: String,
-    pub weather_condition: WeatherType,
+    pub explored_tiles: usize,
+    pub backpack_items: usize,
+    pub energy_consumed: usize,
+    pub energy_recharged: usize,
+    /// Numeric custom metrics, with the last value sent
+    pub custom: BTreeMap<String, f64>,
+}
+
+#[derive(Clone)]
+pub struct DebugMarker {
+    pub coordinates: (usize, usize),
+    pub color: Color,
+    pub label: String,
+}
+
+/// Debug drawings sent by the robot during a tick, shown until its next game info
+#[derive(Clone, Default)]
+pub struct DebugDrawings {
+    pub markers: Vec<DebugMarker>,
+    pub paths: Vec<Vec<(usize, usize)>>,
+    pub highlighted_tiles: Vec<(usize, usize)>,
+    pub messages: Vec<String>,
+}
+
+impl DebugDrawings {
+    pub fn is_empty(&self) -> bool {
+        self.markers.is_empty() && self.paths.is_empty() && self.highlighted_tiles.is_empty() && self.messages.is_empty()
+    }
+}
+
+/// Everything the robot sent to the GUI, read only outside the channel
+pub struct ChannelData {
+    pub(crate) explored_world_map: Vec<Vec<Option<Tile>>>,
+    pub(crate) robot_coordinates: (usize, usize),
+    pub(crate) robot_path: VecDeque<(usize, usize)>,
+    pub(crate) robot_energy: usize,
+    /// Energy of a robot with a full battery
+    pub(crate) robot_max_energy: usize,
+    /// Energy consumed in the last tick, from the `EnergyConsumed` events
+    pub(crate) energy_consumed: usize,
+    /// Energy recharged in the last tick, from the `EnergyRecharged` events
+    pub(crate) energy_recharged: usize,
+    pub(crate) robot_backpack_contents: HashMap<Content, usize>,
+    pub(crate) robot_backpack_size: usize,
+    pub(crate) discoverable_tiles: usize,
+    pub(crate) robot_score: f32,
+    pub(crate) time_of_day: DayTime,
+    pub(crate) time_of_day_string: String,
+    pub(crate) weather_condition: WeatherType,
+    pub(crate) event_log: VecDeque<String>,
+    pub(crate) manual_control_available: bool,
+    pub(crate) manual_mode: bool,
+    /// Number of game infos sent, the history keeps only the last ones
+    pub(crate) ticks: usize,
+    pub(crate) history: VecDeque<TickMetrics>,
+    pub(crate) debug: DebugDrawings,
+    pub(crate) custom_values: BTreeMap<String, CustomValue>,
+    /// Amounts added to (positive) or removed from (negative) the backpack in the last tick, by content name
+    pub(crate) backpack_changes: BTreeMap<String, isize>,
+    /// The robot received `Event::Terminated`
+    pub(crate) terminated: bool,
 }
 
 impl Default for ChannelData {
@@ -36,7 +164,11 @@ impl Default for ChannelData {
         Self {
             explored_world_map: vec![vec![None]],
             robot_coordinates: (0, 0),
+            robot_path: VecDeque::new(),
             robot_energy: 0,
+            robot_max_energy: Energy::default().get_energy_level(),
+            energy_consumed: 0,
+            energy_recharged: 0,
             robot_backpack_contents: HashMap::default(),
             robot_backpack_size: 0,
             discoverable_tiles: usize::MAX,
@@ -44,40 +176,385 @@ impl Default for ChannelData {
             time_of_day: DayTime::Morning,
             time_of_day_string: "00:00".to_string(),
             weather_condition: WeatherType::Sunny,
+            event_log: VecDeque::new(),
+            manual_control_available: false,
+            manual_mode: false,
+            ticks: 0,
+            history: VecDeque::new(),
+            debug: Default::default(),
+            custom_values: BTreeMap::new(),
+            backpack_changes: BTreeMap::new(),
+            terminated: false,
         }
     }
 }
 
+impl ChannelData {
+    /// Tiles seen by the robot, `None` for the unexplored ones
+    pub fn explored_world_map(&self) -> &[Vec<Option<Tile>>] {
+        &self.explored_world_map
+    }
+
+    pub fn robot_coordinates(&self) -> (usize, usize) {
+        self.robot_coordinates
+    }
+
+    /// Last 10000 tiles visited by the robot, in order
+    pub fn robot_path(&self) -> &VecDeque<(usize, usize)> {
+        &self.robot_path
+    }
+
+    pub fn robot_energy(&self) -> usize {
+        self.robot_energy
+    }
+
+    /// Energy of a robot with a full battery
+    pub fn robot_max_energy(&self) -> usize {
+        self.robot_max_energy
+    }
+
+    /// Energy consumed in the last tick, from the `EnergyConsumed` events
+    pub fn energy_consumed(&self) -> usize {
+        self.energy_consumed
+    }
+
+    /// Energy recharged in the last tick, from the `EnergyRecharged` events
+    pub fn energy_recharged(&self) -> usize {
+        self.energy_recharged
+    }
+
+    pub fn robot_backpack_contents(&self) -> &HashMap<Content, usize> {
+        &self.robot_backpack_contents
+    }
+
+    pub fn robot_backpack_size(&self) -> usize {
+        self.robot_backpack_size
+    }
+
+    pub fn discoverable_tiles(&self) -> usize {
+        self.discoverable_tiles
+    }
+
+    pub fn robot_score(&self) -> f32 {
+        self.robot_score
+    }
+
+    pub fn time_of_day(&self) -> DayTime {
+        self.time_of_day
+    }
+
+    /// Time of day as shown by the clock of the HUD, e.g. "08:30"
+    pub fn time_of_day_string(&self) -> &str {
+        &self.time_of_day_string
+    }
+
+    pub fn weather_condition(&self) -> WeatherType {
+        self.weather_condition
+    }
+
+    /// Last lines of the event log, the oldest first
+    pub fn event_log(&self) -> &VecDeque<String> {
+        &self.event_log
+    }
+
+    pub fn manual_control_available(&self) -> bool {
+        self.manual_control_available
+    }
+
+    pub fn manual_mode(&self) -> bool {
+        self.manual_mode
+    }
+
+    /// Number of times the robot sent its game info
+    pub fn ticks(&self) -> usize {
+        self.ticks
+    }
+
+    /// Metrics recorded in the last 10000 game infos, the oldest first
+    pub fn history(&self) -> &VecDeque<TickMetrics> {
+        &self.history
+    }
+
+    /// Debug drawings made by the robot before its last game info
+    pub fn debug(&self) -> &DebugDrawings {
+        &self.debug
+    }
+
+    pub fn custom_values(&self) -> &BTreeMap<String, CustomValue> {
+        &self.custom_values
+    }
+
+    /// Amounts added to (positive) or removed from (negative) the backpack in the last tick, by content name
+    pub fn backpack_changes(&self) -> &BTreeMap<String, isize> {
+        &self.backpack_changes
+    }
+
+    /// The robot received `Event::Terminated`
+    pub fn terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
 pub struct Channel {
     data: ChannelData,
+    commands: VecDeque<ManualCommand>,
+    publisher: Option<Publisher>,
+    pending_debug: DebugDrawings,
+    pending_backpack_changes: BTreeMap<String, isize>,
+    pending_energy_consumed: usize,
+    pending_energy_recharged: usize,
 }
 
 impl Default for Channel {
     fn default() -> Self {
         Self {
             data: Default::default(),
+            commands: VecDeque::new(),
+            publisher: None,
+            pending_debug: Default::default(),
+            pending_backpack_changes: BTreeMap::new(),
+            pending_energy_consumed: 0,
+            pending_energy_recharged: 0,
         }
     }
 }
 
 impl Channel {
-    pub(super) fn receive(&self) -> &ChannelData {
+    pub(crate) fn receive(&self) -> &ChannelData {
         &self.data
     }
 
+    fn record_metrics(&mut self) {
+        let metrics = TickMetrics {
+            tick: self.data.ticks,
+            time_of_day: self.data.time_of_day_string.clone(),
+            weather_condition: weather_name(self.data.weather_condition),
+            robot_row: self.data.robot_coordinates.0,
+            robot_col: self.data.robot_coordinates.1,
+            robot_energy: self.data.robot_energy,
+            robot_score: self.data.robot_score,
+            explored_tiles: self.data.explored_world_map.iter().flatten().filter(|tile| tile.is_some()).count(),
+            backpack_items: self.data.robot_backpack_contents.values().sum(),
+            energy_consumed: self.data.energy_consumed,
+            energy_recharged: self.data.energy_recharged,
+            custom: self.data.custom_values.iter()
+                .filter_map(|(key, value)| value.as_number().map(|number| (key.clone(), number)))
+                .collect(),
+        };
+        if self.data.history.len() == HISTORY_SIZE {
+            self.data.history.pop_front();
+        }
+        self.data.history.push_back(metrics);
+        self.data.ticks += 1;
+    }
+
+    fn push_robot_coordinates(&mut self) {
+        if self.data.robot_path.back() == Some(&self.data.robot_coordinates) {
+            return;
+        }
+        if self.data.robot_path.len() == ROBOT_PATH_SIZE {
+            self.data.robot_path.pop_front();
+        }
+        self.data.robot_path.push_back(self.data.robot_coordinates);
+    }
+
+    /// Publishes every update of the channel on a TCP socket listening on `address` (e.g. "127.0.0.1:7420"),
+    /// see the [`stream`](crate::stream) module for the message format
+    pub fn enable_streaming(&mut self, address: &str) -> Result<(), String> {
+        self.publisher = Some(Publisher::bind(address)?);
+        Ok(())
+    }
+
+    /// Address the stream is published on, with the actual port when streaming was enabled on port 0
+    pub fn streaming_address(&self) -> Option<SocketAddr> {
+        self.publisher.as_ref().and_then(|publisher| publisher.local_addr().ok())
+    }
+
+    /// Adds a line to the event log, it's also listed in the debug panel until the next game info
+    pub fn log(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        self.push_event(text.clone());
+        self.pending_debug.messages.push(text);
+    }
+
+    fn push_event(&mut self, text: String) {
+        if self.data.event_log.len() == EVENT_LOG_SIZE {
+            self.data.event_log.pop_front();
+        }
+        self.data.event_log.push_back(text.clone());
+        // The clients accepted while publishing get the event in the snapshot of the event log
+        if let Some(publisher) = self.publisher.as_mut() {
+            publisher.publish_event(&self.data, &text);
+        }
+    }
+
+    /// Shows a custom metric in the telemetry panel, e.g. the current goal or the state of the robot.
+    ///
+    /// The value is kept until it's sent again, numeric values are recorded in the history
+    /// together with energy and score every time the game info is sent.
+    pub fn send_custom(&mut self, key: &str, value: impl Into<CustomValue>) {
+        self.data.custom_values.insert(key.to_string(), value.into());
+    }
+
+    /// Draws a marker with a label on a tile.
+    ///
+    /// The debug drawings made during a tick are shown after the next call to [`Channel::send_game_info`]
+    /// and replaced by the ones made before the following call, so draw them before sending the game info.
+    pub fn draw_marker(&mut self, coordinates: (usize, usize), color: Color, label: &str) {
+        self.pending_debug.markers.push(DebugMarker {
+            coordinates,
+            color,
+            label: label.to_string(),
+        });
+    }
+
+    /// Draws a line through the given tiles, e.g. the path planned by the robot.
+    /// See [`Channel::draw_marker`] for how long it's shown.
+    pub fn draw_path(&mut self, path: Vec<(usize, usize)>) {
+        self.pending_debug.paths.push(path);
+    }
+
+    /// Highlights the given tiles, e.g. the targets considered by the robot.
+    /// See [`Channel::draw_marker`] for how long they're shown.
+    pub fn highlight_tiles(&mut self, tiles: &[(usize, usize)]) {
+        self.pending_debug.highlighted_tiles.extend_from_slice(tiles);
+    }
+
+    /// Forwards an event received by the robot, call it from `Runnable::handle_event`.
+    ///
+    /// Like the debug drawings, the changes caused by the events of a tick are shown after the next game info.
+    pub fn send_event(&mut self, event: &Event) {
+        match event {
+            Event::AddedToBackpack(content, amount) => {
+                *self.pending_backpack_changes.entry(content_name(content).to_string()).or_insert(0) += *amount as isize;
+            }
+            Event::RemovedFromBackpack(content, amount) => {
+                *self.pending_backpack_changes.entry(content_name(content).to_string()).or_insert(0) -= *amount as isize;
+            }
+            Event::EnergyConsumed(amount) => self.pending_energy_consumed += amount,
+            Event::EnergyRecharged(amount) => self.pending_energy_recharged += amount,
+            Event::Terminated => {
+                // No game info follows the termination, the viewers get it right away
+                self.data.terminated = true;
+                self.push_event("The robot terminated".to_string());
+                if let Some(publisher) = self.publisher.as_mut() {
+                    publisher.publish_game_info(&self.data);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn enable_manual_control(&mut self) {
+        self.data.manual_control_available = true;
+    }
+
+    pub(crate) fn is_manual_mode_on(&self) -> bool {
+        self.data.manual_mode
+    }
+
+    pub(crate) fn push_command(&mut self, command: ManualCommand) {
+        if !self.data.manual_control_available {
+            return;
+        }
+
+        match command {
+            ManualCommand::ToggleManualMode => {
+                self.data.manual_mode = !self.data.manual_mode;
+                self.commands.clear();
+                self.push_event(format!("Manual control {}", if self.data.manual_mode { "on" } else { "off" }));
+            }
+            command if self.data.manual_mode => self.commands.push_back(command),
+            _ => {}
+        }
+    }
+
+    pub(crate) fn take_command(&mut self) -> Option<ManualCommand> {
+        self.commands.pop_front()
+    }
+
+    /// Updates the data with a message received from a stream, used by the remote viewer
+    pub(crate) fn apply_stream_message(&mut self, message: StreamMessage) {
+        match message {
+            StreamMessage::Snapshot { rows, cols, tiles, robot, weather, events } => {
+                self.data.explored_world_map = vec![vec![None; cols]; rows];
+                self.apply_tiles(tiles);
+                self.data.event_log = events.into_iter().rev().take(EVENT_LOG_SIZE).rev().collect();
+                self.apply_weather(weather);
+                self.apply_robot(robot);
+            }
+            StreamMessage::MapDelta { tiles } => self.apply_tiles(tiles),
+            StreamMessage::Robot(robot) => self.apply_robot(robot),
+            StreamMessage::Weather(weather) => self.apply_weather(weather),
+            StreamMessage::Event { text } => self.push_event(text),
+        }
+    }
+
+    fn apply_tiles(&mut self, tiles: Vec<StreamTile>) {
+        for stream_tile in tiles {
+            let tile = self.data.explored_world_map
+                .get_mut(stream_tile.row)
+                .and_then(|row| row.get_mut(stream_tile.col));
+            if let Some(tile) = tile {
+                *tile = stream_tile.to_tile();
+            }
+        }
+    }
+
+    fn apply_robot(&mut self, robot: RobotState) {
+        self.data.robot_coordinates = (robot.row, robot.col);
+        self.push_robot_coordinates();
+        self.data.robot_energy = robot.energy;
+        self.data.robot_backpack_contents = robot.backpack.iter()
+            .filter_map(|(name, amount)| content_from_name(name, 0, None).map(|content| (content, *amount)))
+            .collect();
+        self.data.robot_backpack_size = robot.backpack_size;
+        self.data.discoverable_tiles = robot.discoverable_tiles;
+        self.data.robot_score = robot.score;
+        self.data.custom_values = robot.custom;
+        self.data.backpack_changes = robot.backpack_changes;
+        self.data.energy_consumed = robot.energy_consumed;
+        self.data.energy_recharged = robot.energy_recharged;
+        self.data.terminated = robot.terminated;
+        self.record_metrics();
+    }
+
+    fn apply_weather(&mut self, weather: WeatherState) {
+        self.data.time_of_day_string = weather.time_of_day;
+        if let Some(day_time) = day_time_from_name(&weather.day_time) {
+            self.data.time_of_day = day_time;
+        }
+        if let Some(weather_condition) = weather_from_name(&weather.weather_condition) {
+            self.data.weather_condition = weather_condition;
+        }
+    }
+
     pub fn send_game_info(&mut self, robot: & impl Runnable, world: &mut World) {
         self.data.explored_world_map = robot_map(world).expect("Problem calling robot_map (probably Mutex problems)");
         self.data.robot_coordinates = (robot.get_coordinate().get_row(), robot.get_coordinate().get_col());
+        self.push_robot_coordinates();
         self.data.robot_energy = robot.get_energy().get_energy_level();
         self.data.robot_backpack_contents = robot.get_backpack().get_contents().clone();
         self.data.robot_backpack_size = robot.get_backpack().get_size();
         self.data.discoverable_tiles = world.get_discoverable();
         self.data.robot_score = get_score(world);
+        self.data.debug = std::mem::take(&mut self.pending_debug);
+        self.data.backpack_changes = std::mem::take(&mut self.pending_backpack_changes);
+        self.data.backpack_changes.retain(|_, change| *change != 0);
+        self.data.energy_consumed = std::mem::take(&mut self.pending_energy_consumed);
+        self.data.energy_recharged = std::mem::take(&mut self.pending_energy_recharged);
+        self.record_metrics();
+        if let Some(publisher) = self.publisher.as_mut() {
+            publisher.publish_game_info(&self.data);
+        }
     }
 
     pub fn send_weather_info(&mut self, weather: EnvironmentalConditions) {
         self.data.time_of_day = weather.get_time_of_day();
         self.data.time_of_day_string = weather.get_time_of_day_string();
         self.data.weather_condition = weather.get_weather_condition();
+        if let Some(publisher) = self.publisher.as_mut() {
+            publisher.publish_weather_info(&self.data);
+        }
     }
 }
\ No newline at end of file
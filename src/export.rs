@@ -0,0 +1,708 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fs;
+use std::path::Path;
+
+use robotics_lib::world::tile::{Content, Tile, TileType};
+use serde::Serialize;
+
+use crate::channel::{Channel, ChannelData, CustomValue, TickMetrics};
+use crate::world_info::{content_amount, content_capacity, content_cube_size, content_fill, content_name, content_stack_height, tile_type_name, weather_name, FILL_GAUGE_OFFSET, FILL_GAUGE_SIZE};
+
+fn tile_color(tile_type: &TileType) -> [u8; 4] {
+    match tile_type {
+        TileType::DeepWater => [20, 40, 140, 255],
+        TileType::ShallowWater => [60, 110, 220, 255],
+        TileType::Sand => [220, 205, 140, 255],
+        TileType::Grass => [70, 160, 60, 255],
+        TileType::Street => [150, 120, 80, 255],
+        TileType::Hill => [120, 90, 60, 255],
+        TileType::Mountain => [125, 125, 125, 255],
+        TileType::Snow => [240, 245, 250, 255],
+        TileType::Lava => [230, 90, 20, 255],
+        TileType::Teleport(_) => [120, 220, 220, 255],
+        TileType::Wall => [80, 80, 80, 255],
+    }
+}
+
+fn content_color(content: &Content) -> Option<[u8; 4]> {
+    match content {
+        Content::Rock(_) => Some([90, 90, 90, 255]),
+        Content::Tree(_) => Some([100, 60, 20, 255]),
+        Content::Garbage(_) => Some([30, 30, 30, 255]),
+        Content::Fire => Some([255, 60, 0, 255]),
+        Content::Coin(_) => Some([255, 215, 0, 255]),
+        Content::Bin(_) => Some([110, 110, 130, 255]),
+        Content::Crate(_) => Some([160, 110, 50, 255]),
+        Content::Bank(_) => Some([250, 235, 90, 255]),
+        Content::Water(_) => Some([0, 120, 255, 255]),
+        Content::Market(_) => Some([30, 200, 110, 255]),
+        Content::Fish(_) => Some([255, 140, 60, 255]),
+        Content::Building => Some([170, 70, 60, 255]),
+        Content::Bush(_) => Some([40, 110, 40, 255]),
+        Content::JollyBlock(_) => Some([255, 160, 0, 255]),
+        Content::Scarecrow => Some([210, 190, 90, 255]),
+        Content::None => None,
+    }
+}
+
+const PATH_COLOR: [u8; 4] = [220, 0, 0, 255];
+const ROBOT_COLOR: [u8; 4] = [255, 0, 255, 255];
+
+struct Canvas {
+    width: usize,
+    height: usize,
+    bytes: Vec<u8>,
+}
+
+impl Canvas {
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: [u8; 4]) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                let index = (row * self.width + column) * 4;
+                self.bytes[index..index + 4].copy_from_slice(&color);
+            }
+        }
+    }
+}
+
+/// Saves the explored map as a PNG image, each tile is a square of `block_size` pixels.
+///
+/// Rows of the map are rows of the image and unexplored tiles are transparent.
+/// Tiles with a content get a colored mark in the middle and `robot_path` is drawn on top,
+/// ending with a mark on the last position of the robot.
+pub fn export_map_png(explored_world_map: &[Vec<Option<Tile>>], robot_path: &[(usize, usize)], block_size: usize, path: &str) -> Result<(), String> {
+    let block_size = block_size.max(1);
+    let rows = explored_world_map.len();
+    let columns = explored_world_map.iter().map(|row| row.len()).max().unwrap_or(0);
+    let mut canvas = Canvas {
+        width: columns * block_size,
+        height: rows * block_size,
+        bytes: vec![0; columns * block_size * rows * block_size * 4],
+    };
+
+    let mark_size = (block_size / 3).max(1);
+    let mark_offset = (block_size - mark_size) / 2;
+    for (x, row) in explored_world_map.iter().enumerate() {
+        for (z, tile) in row.iter().enumerate() {
+            if let Some(tile) = tile {
+                canvas.fill(z * block_size, x * block_size, block_size, block_size, tile_color(&tile.tile_type));
+                if let Some(color) = content_color(&tile.content) {
+                    canvas.fill(z * block_size + mark_offset, x * block_size + mark_offset, mark_size, mark_size, color);
+                }
+            }
+        }
+    }
+
+    // The path goes through the center of the tiles, consecutive positions are neighbours
+    // except after a teleport, where the jump is drawn as a straight line anyway
+    let line_width = (block_size / 4).max(1);
+    let center = |(x, z): (usize, usize)| (z * block_size + block_size / 2, x * block_size + block_size / 2);
+    for step in robot_path.windows(2) {
+        let ((from_x, from_y), (to_x, to_y)) = (center(step[0]), center(step[1]));
+        let steps = from_x.abs_diff(to_x).max(from_y.abs_diff(to_y)).max(1);
+        for i in 0..=steps {
+            let t = i as f32 / steps as f32;
+            let x = from_x as f32 + (to_x as f32 - from_x as f32) * t;
+            let y = from_y as f32 + (to_y as f32 - from_y as f32) * t;
+            canvas.fill(x as usize, y as usize, line_width, line_width, PATH_COLOR);
+        }
+    }
+    if let Some(last) = robot_path.last() {
+        let (x, y) = center(*last);
+        canvas.fill(x.saturating_sub(mark_size), y.saturating_sub(mark_size), mark_size * 2, mark_size * 2, ROBOT_COLOR);
+    }
+
+    image::save_buffer(path, &canvas.bytes, canvas.width as u32, canvas.height as u32, image::ColorType::Rgba8)
+        .map_err(|error| format!("Error exporting the map to {}: {}", path, error))
+}
+
+#[derive(Serialize)]
+struct TileExport {
+    row: usize,
+    col: usize,
+    tile_type: &'static str,
+    teleport_active: Option<bool>,
+    elevation: usize,
+    content: &'static str,
+    content_amount: Option<usize>,
+    /// Set only for the contents holding a range, `content_amount` is the start of the range and this the end
+    content_capacity: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct RobotExport<'a> {
+    coordinates: (usize, usize),
+    energy: usize,
+    score: f32,
+    backpack_size: usize,
+    backpack_contents: BTreeMap<&'static str, usize>,
+    discoverable_tiles: usize,
+    time_of_day: String,
+    weather_condition: &'static str,
+    custom: &'a BTreeMap<String, CustomValue>,
+}
+
+#[derive(Serialize)]
+struct RunExport<'a> {
+    robot: RobotExport<'a>,
+    path: &'a VecDeque<(usize, usize)>,
+    history: &'a VecDeque<TickMetrics>,
+    event_log: Vec<&'a str>,
+    explored_map: Vec<TileExport>,
+}
+
+fn explored_tiles(explored_world_map: &[Vec<Option<Tile>>]) -> Vec<TileExport> {
+    explored_world_map.iter()
+        .enumerate()
+        .flat_map(|(row, tiles)| tiles.iter().enumerate().map(move |(col, tile)| (row, col, tile)))
+        .filter_map(|(row, col, tile)| tile.as_ref().map(|tile| TileExport {
+            row,
+            col,
+            tile_type: tile_type_name(&tile.tile_type),
+            teleport_active: match tile.tile_type {
+                TileType::Teleport(active) => Some(active),
+                _ => None,
+            },
+            elevation: tile.elevation,
+            content: content_name(&tile.content),
+            content_amount: content_amount(&tile.content),
+            content_capacity: content_capacity(&tile.content),
+        }))
+        .collect()
+}
+
+fn write_file(path: &str, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|error| format!("Error writing {}: {}", path, error))
+}
+
+fn write_run_json(data: &ChannelData, path: &str) -> Result<(), String> {
+    let export = RunExport {
+        robot: RobotExport {
+            coordinates: data.robot_coordinates,
+            energy: data.robot_energy,
+            score: data.robot_score,
+            backpack_size: data.robot_backpack_size,
+            backpack_contents: data.robot_backpack_contents.iter()
+                .map(|(content, amount)| (content_name(content), *amount))
+                .collect(),
+            discoverable_tiles: data.discoverable_tiles,
+            time_of_day: data.time_of_day_string.clone(),
+            weather_condition: weather_name(data.weather_condition),
+            custom: &data.custom_values,
+        },
+        path: &data.robot_path,
+        history: &data.history,
+        event_log: data.event_log.iter().map(|entry| entry.as_str()).collect(),
+        explored_map: explored_tiles(&data.explored_world_map),
+    };
+
+    let json = serde_json::to_string_pretty(&export).map_err(|error| format!("Error serializing the run: {}", error))?;
+    write_file(path, &json)
+}
+
+/// Quotes a CSV field if it contains a separator, a quote or a line break, as in RFC 4180
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn write_metrics_csv(data: &ChannelData, path: &str) -> Result<(), String> {
+    // A custom metric gets a column even if it was sent only in some of the ticks
+    let custom_keys = data.history.iter()
+        .flat_map(|metrics| metrics.custom.keys())
+        .collect::<BTreeSet<_>>();
+
+    let mut csv = String::from("tick,time_of_day,weather_condition,robot_row,robot_col,robot_energy,robot_score,explored_tiles,backpack_items,energy_consumed,energy_recharged");
+    for key in custom_keys.iter() {
+        csv.push_str(&format!(",{}", csv_field(key)));
+    }
+    csv.push('\n');
+
+    for metrics in data.history.iter() {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            metrics.tick,
+            metrics.time_of_day,
+            metrics.weather_condition,
+            metrics.robot_row,
+            metrics.robot_col,
+            metrics.robot_energy,
+            metrics.robot_score,
+            metrics.explored_tiles,
+            metrics.backpack_items,
+            metrics.energy_consumed,
+            metrics.energy_recharged
+        ));
+        for key in custom_keys.iter() {
+            csv.push_str(&format!(",{}", metrics.custom.get(*key).map_or(String::new(), |value| csv_field(&value.to_string()))));
+        }
+        csv.push('\n');
+    }
+
+    write_file(path, &csv)
+}
+
+fn write_map_csv(data: &ChannelData, path: &str) -> Result<(), String> {
+    let mut csv = String::from("row,col,tile_type,teleport_active,elevation,content,content_amount,content_capacity\n");
+    for tile in explored_tiles(&data.explored_world_map) {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{}\n",
+            tile.row,
+            tile.col,
+            tile.tile_type,
+            tile.teleport_active.map_or(String::new(), |active| active.to_string()),
+            tile.elevation,
+            tile.content,
+            tile.content_amount.map_or(String::new(), |amount| amount.to_string()),
+            tile.content_capacity.map_or(String::new(), |capacity| capacity.to_string())
+        ));
+    }
+
+    write_file(path, &csv)
+}
+
+pub(crate) fn export_run_data(data: &ChannelData, prefix: &str) -> Result<(), String> {
+    write_run_json(data, &format!("{}_run.json", prefix))?;
+    write_metrics_csv(data, &format!("{}_metrics.csv", prefix))?;
+    write_map_csv(data, &format!("{}_map.csv", prefix))
+}
+
+/// Saves the current state of the robot, its history, the event log and the explored tiles as JSON
+pub fn export_run_json(channel: &Channel, path: &str) -> Result<(), String> {
+    write_run_json(channel.receive(), path)
+}
+
+/// Saves the metrics recorded at every tick as CSV, one row per tick
+pub fn export_metrics_csv(channel: &Channel, path: &str) -> Result<(), String> {
+    write_metrics_csv(channel.receive(), path)
+}
+
+/// Saves the explored tiles as CSV, one row per tile
+pub fn export_map_csv(channel: &Channel, path: &str) -> Result<(), String> {
+    write_map_csv(channel.receive(), path)
+}
+
+/// Saves everything in `<prefix>_run.json`, `<prefix>_metrics.csv` and `<prefix>_map.csv`
+pub fn export_run(channel: &Channel, prefix: &str) -> Result<(), String> {
+    export_run_data(channel.receive(), prefix)
+}
+
+/// Texture of an exported material, the file name is the one in the assets folder
+struct ObjTexture {
+    file_name: &'static str,
+    bytes: &'static [u8],
+}
+
+macro_rules! obj_texture {
+    ($folder:literal, $file_name:literal) => {
+        ObjTexture {
+            file_name: $file_name,
+            bytes: include_bytes!(concat!("../assets/", $folder, "/", $file_name)),
+        }
+    };
+}
+
+/// Material used by a tile, a tinted material has its own name so the tint can be set in the .mtl file
+fn tile_material(tile_type: &TileType) -> (&'static str, ObjTexture, f32) {
+    match tile_type {
+        TileType::DeepWater => ("deep_water", obj_texture!("tiles", "underwater_opaque.png"), 0.5),
+        TileType::ShallowWater => ("shallow_water", obj_texture!("tiles", "underwater_opaque.png"), 1.0),
+        TileType::Sand => ("sand", obj_texture!("tiles", "sand.png"), 1.0),
+        TileType::Grass => ("grass", obj_texture!("tiles", "green_concrete_powder.png"), 1.0),
+        TileType::Street => ("street", obj_texture!("tiles", "dirt_path_top.png"), 1.0),
+        TileType::Hill => ("hill", obj_texture!("tiles", "dirt.png"), 1.0),
+        TileType::Mountain => ("mountain", obj_texture!("tiles", "stone.png"), 1.0),
+        TileType::Snow => ("snow", obj_texture!("tiles", "snow.png"), 1.0),
+        TileType::Lava => ("lava", obj_texture!("tiles", "lava.png"), 1.0),
+        TileType::Teleport(true) => ("teleport", obj_texture!("tiles", "beacon.png"), 1.0),
+        TileType::Teleport(false) => ("teleport_inactive", obj_texture!("tiles", "beacon.png"), 0.3),
+        TileType::Wall => ("wall", obj_texture!("tiles", "stone_bricks.png"), 1.0),
+    }
+}
+
+fn content_material(content: &Content) -> Option<(&'static str, ObjTexture, f32)> {
+    match content {
+        Content::Rock(_) => Some(("rock", obj_texture!("contents", "cobblestone.png"), 1.0)),
+        Content::Tree(_) => Some(("tree", obj_texture!("contents", "oak_log.png"), 1.0)),
+        Content::Garbage(_) => Some(("garbage", obj_texture!("contents", "coal_block.png"), 1.0)),
+        Content::Fire => Some(("fire", obj_texture!("contents", "fire.png"), 1.0)),
+        Content::Coin(_) => Some(("coin", obj_texture!("contents", "gold_ore.png"), 1.0)),
+        Content::Bin(_) => Some(("bin", obj_texture!("contents", "dropper_front_vertical.png"), 1.0)),
+        Content::Crate(_) => Some(("crate", obj_texture!("contents", "barrel.png"), 1.0)),
+        Content::Bank(_) => Some(("bank", obj_texture!("contents", "gold_block.png"), 1.0)),
+        Content::Water(_) => Some(("water", obj_texture!("contents", "water.png"), 1.0)),
+        Content::Market(_) => Some(("market", obj_texture!("contents", "emerald_block.png"), 1.0)),
+        Content::Fish(_) => Some(("fish", obj_texture!("contents", "orange_glazed_terracotta.png"), 1.0)),
+        Content::Building => Some(("building", obj_texture!("contents", "bricks.png"), 1.0)),
+        Content::Bush(_) => Some(("bush", obj_texture!("contents", "azalea_top.png"), 0.8)),
+        Content::JollyBlock(_) => Some(("jolly_block", obj_texture!("contents", "jack_o_lantern.png"), 1.0)),
+        Content::Scarecrow => Some(("scarecrow", obj_texture!("contents", "hay_block_side.png"), 1.0)),
+        Content::None => None,
+    }
+}
+
+// Corners of the unit cube for each face, counterclockwise seen from outside, with their texture coordinates
+const BOX_FACES: [[([f32; 3], [f32; 2]); 4]; 6] = [
+    [([0., 1., 0.], [0., 0.]), ([0., 1., 1.], [0., 1.]), ([1., 1., 1.], [1., 1.]), ([1., 1., 0.], [1., 0.])],
+    [([0., 0., 0.], [0., 0.]), ([1., 0., 0.], [1., 0.]), ([1., 0., 1.], [1., 1.]), ([0., 0., 1.], [0., 1.])],
+    [([1., 0., 0.], [0., 0.]), ([1., 1., 0.], [0., 1.]), ([1., 1., 1.], [1., 1.]), ([1., 0., 1.], [1., 0.])],
+    [([0., 0., 0.], [1., 0.]), ([0., 0., 1.], [0., 0.]), ([0., 1., 1.], [0., 1.]), ([0., 1., 0.], [1., 1.])],
+    [([0., 0., 1.], [0., 0.]), ([1., 0., 1.], [1., 0.]), ([1., 1., 1.], [1., 1.]), ([0., 1., 1.], [0., 1.])],
+    [([0., 0., 0.], [1., 0.]), ([0., 1., 0.], [1., 1.]), ([1., 1., 0.], [0., 1.]), ([1., 0., 0.], [0., 0.])],
+];
+
+/// Builds the contents of the .obj and .mtl files, indices in .obj files start from 1
+struct ObjBuilder {
+    obj: String,
+    mtl: String,
+    vertices: usize,
+    uvs: usize,
+    materials: BTreeSet<&'static str>,
+    textures: BTreeMap<&'static str, &'static [u8]>,
+    current_material: &'static str,
+}
+
+impl ObjBuilder {
+    fn new(mtl_file_name: &str) -> Self {
+        Self {
+            obj: format!("# Explored world exported by Olympus\nmtllib {}\n", mtl_file_name),
+            mtl: String::from("# Materials of the explored world exported by Olympus\n"),
+            vertices: 0,
+            uvs: 0,
+            materials: BTreeSet::new(),
+            textures: BTreeMap::new(),
+            current_material: "",
+        }
+    }
+
+    fn add_material(&mut self, name: &'static str, color: [f32; 3], alpha: f32, texture: Option<(ObjTexture, &str)>) {
+        if !self.materials.insert(name) {
+            return;
+        }
+
+        self.mtl.push_str(&format!("\nnewmtl {}\nKd {} {} {}\nd {}\n", name, color[0], color[1], color[2], alpha));
+        if let Some((texture, folder)) = texture {
+            self.mtl.push_str(&format!("map_Kd {}/{}\n", folder, texture.file_name));
+            self.textures.insert(texture.file_name, texture.bytes);
+        }
+    }
+
+    fn use_material(&mut self, name: &'static str) {
+        if self.current_material != name {
+            self.obj.push_str(&format!("usemtl {}\n", name));
+            self.current_material = name;
+        }
+    }
+
+    fn object(&mut self, name: &str) {
+        self.obj.push_str(&format!("o {}\n", name));
+        self.current_material = "";
+    }
+
+    fn add_box(&mut self, min: [f32; 3], size: [f32; 3]) {
+        for face in BOX_FACES.iter() {
+            for (corner, uv) in face.iter() {
+                self.obj.push_str(&format!(
+                    "v {} {} {}\nvt {} {}\n",
+                    min[0] + corner[0] * size[0],
+                    min[1] + corner[1] * size[1],
+                    min[2] + corner[2] * size[2],
+                    uv[0],
+                    uv[1]
+                ));
+            }
+            let (v, vt) = (self.vertices, self.uvs);
+            self.obj.push_str(&format!(
+                "f {}/{} {}/{} {}/{} {}/{}\n",
+                v + 1, vt + 1, v + 2, vt + 2, v + 3, vt + 3, v + 4, vt + 4
+            ));
+            self.vertices += 4;
+            self.uvs += 4;
+        }
+    }
+
+    fn add_line(&mut self, points: &[[f32; 3]]) {
+        if points.len() < 2 {
+            return;
+        }
+
+        for point in points.iter() {
+            self.obj.push_str(&format!("v {} {} {}\n", point[0], point[1], point[2]));
+        }
+        let indices = (self.vertices + 1..=self.vertices + points.len())
+            .map(|index| index.to_string())
+            .collect::<Vec<_>>();
+        self.obj.push_str(&format!("l {}\n", indices.join(" ")));
+        self.vertices += points.len();
+    }
+}
+
+/// Saves the explored map as a Wavefront OBJ model that can be opened in Blender or other 3D viewers.
+///
+/// The scene matches the one drawn by the visualizer: tiles are boxes as tall as their elevation,
+/// contents are stacks of cubes as tall as their amount, containers and markets have a gauge as tall
+/// as their fill level and `robot_path` is a polyline ending with a box on the robot.
+/// Next to `path` are written the `.mtl` file with the materials and a folder with the textures.
+pub fn export_map_obj(explored_world_map: &[Vec<Option<Tile>>], robot_path: &[(usize, usize)], path: &str) -> Result<(), String> {
+    let obj_path = Path::new(path);
+    let stem = obj_path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("world");
+    let mtl_path = obj_path.with_extension("mtl");
+    let textures_folder = format!("{}_textures", stem);
+    let textures_path = obj_path.with_file_name(&textures_folder);
+    let mtl_file_name = mtl_path.file_name().and_then(|name| name.to_str()).unwrap_or("world.mtl").to_string();
+
+    let mut builder = ObjBuilder::new(&mtl_file_name);
+    let elevation = |x: usize, z: usize| -> f32 {
+        explored_world_map.get(x)
+            .and_then(|row| row.get(z))
+            .and_then(|tile| tile.as_ref())
+            .map_or(1, |tile| tile.elevation.max(1)) as f32
+    };
+
+    builder.object("terrain");
+    for (x, row) in explored_world_map.iter().enumerate() {
+        for (z, tile) in row.iter().enumerate() {
+            if let Some(tile) = tile {
+                let (name, texture, tint) = tile_material(&tile.tile_type);
+                builder.add_material(name, [tint; 3], 1.0, Some((texture, &textures_folder)));
+                builder.use_material(name);
+                builder.add_box([x as f32, 0.0, z as f32], [1.0, elevation(x, z), 1.0]);
+            }
+        }
+    }
+
+    builder.object("contents");
+    for (x, row) in explored_world_map.iter().enumerate() {
+        for (z, tile) in row.iter().enumerate() {
+            let Some(tile) = tile else {
+                continue;
+            };
+            let Some((name, texture, tint)) = content_material(&tile.content) else {
+                continue;
+            };
+
+            // Water is see-through like the wireframe cube drawn by the visualizer, and never stacked
+            let water = matches!(tile.content, Content::Water(_));
+            let alpha = if water { 0.5 } else { 1.0 };
+            builder.add_material(name, [tint; 3], alpha, Some((texture, &textures_folder)));
+            builder.use_material(name);
+            let stack_height = if water { 1 } else { content_stack_height(&tile.content) };
+            let cube_size = content_cube_size(stack_height);
+            let corner = 0.5 - cube_size / 2.0;
+            for level in 0..stack_height {
+                builder.add_box(
+                    [x as f32 + corner, elevation(x, z) + level as f32 * cube_size, z as f32 + corner],
+                    [cube_size; 3]
+                );
+            }
+
+            if let Some(fill) = content_fill(&tile.content).filter(|fill| *fill > 0.0) {
+                let (width, height) = FILL_GAUGE_SIZE;
+                builder.add_material("fill_gauge", [0.1, 0.8, 0.2], 1.0, None);
+                builder.use_material("fill_gauge");
+                builder.add_box(
+                    [x as f32 + 0.5 + FILL_GAUGE_OFFSET - width / 2.0, elevation(x, z), z as f32 + 0.5 - width / 2.0],
+                    [width, height * fill, width]
+                );
+            }
+        }
+    }
+
+    if let Some((x, z)) = robot_path.last() {
+        builder.object("robot_path");
+        builder.add_material("robot_path", [0.86, 0.0, 0.0], 1.0, None);
+        builder.use_material("robot_path");
+        let points = robot_path.iter()
+            .map(|(x, z)| [*x as f32 + 0.5, elevation(*x, *z) + 0.05, *z as f32 + 0.5])
+            .collect::<Vec<_>>();
+        builder.add_line(&points);
+
+        builder.object("robot");
+        builder.add_material("robot", [1.0; 3], 1.0, Some((obj_texture!("robot", "creeper.png"), &textures_folder)));
+        builder.use_material("robot");
+        builder.add_box([*x as f32 + 0.3, elevation(*x, *z), *z as f32 + 0.3], [0.4, 0.8, 0.4]);
+    }
+
+    fs::create_dir_all(&textures_path).map_err(|error| format!("Error creating {}: {}", textures_path.display(), error))?;
+    for (file_name, bytes) in builder.textures.iter() {
+        let texture_path = textures_path.join(file_name);
+        fs::write(&texture_path, bytes).map_err(|error| format!("Error writing {}: {}", texture_path.display(), error))?;
+    }
+    write_file(&mtl_path.to_string_lossy(), &builder.mtl)?;
+    write_file(path, &builder.obj)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap, VecDeque};
+    use std::fs;
+    use std::path::PathBuf;
+
+    use robotics_lib::world::tile::{Content, Tile, TileType};
+
+    use super::{csv_field, export_map_obj, export_map_png, export_run_data, ROBOT_COLOR};
+    use crate::channel::{ChannelData, CustomValue, TickMetrics};
+    use crate::test_fixtures::{explored, metrics, tile};
+
+    fn metrics_at(tick: usize, time_of_day: &str, coordinates: (usize, usize), custom: BTreeMap<String, f64>) -> TickMetrics {
+        TickMetrics {
+            time_of_day: time_of_day.to_string(),
+            robot_row: coordinates.0,
+            robot_col: coordinates.1,
+            robot_energy: 100 - tick * 10,
+            robot_score: tick as f32 * 10.5,
+            explored_tiles: tick + 1,
+            backpack_items: tick,
+            energy_consumed: tick * 10,
+            custom,
+            ..metrics(tick)
+        }
+    }
+
+    fn run_data() -> ChannelData {
+        ChannelData {
+            explored_world_map: vec![
+                vec![Some(Tile { elevation: 2, ..tile(TileType::Grass, Content::Rock(3)) }), None],
+                vec![explored(TileType::Street, Content::Bin(4..10)), explored(TileType::Teleport(true), Content::None)],
+            ],
+            robot_coordinates: (1, 1),
+            robot_path: VecDeque::from([(0, 0), (1, 1)]),
+            robot_energy: 90,
+            robot_score: 10.5,
+            robot_backpack_contents: HashMap::from([(Content::Rock(0), 1)]),
+            robot_backpack_size: 20,
+            time_of_day_string: "08:10".to_string(),
+            event_log: VecDeque::from(["Started".to_string()]),
+            custom_values: BTreeMap::from([("state".to_string(), CustomValue::Text("exploring".to_string()))]),
+            ticks: 2,
+            history: VecDeque::from([
+                metrics_at(0, "08:00", (0, 0), BTreeMap::new()),
+                metrics_at(1, "08:10", (1, 1), BTreeMap::from([("goal, \"x\"".to_string(), 1.5)])),
+            ]),
+            ..Default::default()
+        }
+    }
+
+    /// Prefix of the exported files in a directory removed when the test ends
+    struct ExportDir(PathBuf);
+
+    impl ExportDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("olympus_{}_{}", name, std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn prefix(&self) -> String {
+            self.0.join("run").to_str().unwrap().to_string()
+        }
+
+        fn read(&self, file_name: &str) -> String {
+            fs::read_to_string(self.0.join(file_name)).unwrap()
+        }
+    }
+
+    impl Drop for ExportDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn quotes_only_the_fields_that_need_it() {
+        assert_eq!(csv_field("energy"), "energy");
+        assert_eq!(csv_field("goal, x"), "\"goal, x\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn exports_the_metrics_and_the_map_as_csv() {
+        let dir = ExportDir::new("export_csv");
+        export_run_data(&run_data(), &dir.prefix()).unwrap();
+
+        assert_eq!(dir.read("run_metrics.csv"), concat!(
+            "tick,time_of_day,weather_condition,robot_row,robot_col,robot_energy,robot_score,explored_tiles,backpack_items,energy_consumed,energy_recharged,\"goal, \"\"x\"\"\"\n",
+            "0,08:00,Sunny,0,0,100,0,1,0,0,0,\n",
+            "1,08:10,Sunny,1,1,90,10.5,2,1,10,0,1.5\n",
+        ));
+        assert_eq!(dir.read("run_map.csv"), concat!(
+            "row,col,tile_type,teleport_active,elevation,content,content_amount,content_capacity\n",
+            "0,0,Grass,,2,Rock,3,\n",
+            "1,0,Street,,0,Bin,4,10\n",
+            "1,1,Teleport,true,0,None,,\n",
+        ));
+    }
+
+    #[test]
+    fn exports_the_run_as_json() {
+        let dir = ExportDir::new("export_json");
+        export_run_data(&run_data(), &dir.prefix()).unwrap();
+
+        let json = serde_json::from_str::<serde_json::Value>(&dir.read("run_run.json")).unwrap();
+        assert_eq!(json["robot"]["coordinates"], serde_json::json!([1, 1]));
+        assert_eq!(json["robot"]["energy"], 90);
+        assert_eq!(json["robot"]["backpack_contents"], serde_json::json!({ "Rock": 1 }));
+        assert_eq!(json["robot"]["time_of_day"], "08:10");
+        assert_eq!(json["robot"]["weather_condition"], "Sunny");
+        assert_eq!(json["robot"]["custom"], serde_json::json!({ "state": "exploring" }));
+        assert_eq!(json["path"], serde_json::json!([[0, 0], [1, 1]]));
+        assert_eq!(json["event_log"], serde_json::json!(["Started"]));
+        assert_eq!(json["history"].as_array().unwrap().len(), 2);
+        assert_eq!(json["history"][1]["custom"], serde_json::json!({ "goal, \"x\"": 1.5 }));
+        assert_eq!(json["explored_map"], serde_json::json!([
+            { "row": 0, "col": 0, "tile_type": "Grass", "teleport_active": null, "elevation": 2, "content": "Rock", "content_amount": 3, "content_capacity": null },
+            { "row": 1, "col": 0, "tile_type": "Street", "teleport_active": null, "elevation": 0, "content": "Bin", "content_amount": 4, "content_capacity": 10 },
+            { "row": 1, "col": 1, "tile_type": "Teleport", "teleport_active": true, "elevation": 0, "content": "None", "content_amount": null, "content_capacity": null }
+        ]));
+    }
+
+    #[test]
+    fn exports_the_map_as_png() {
+        let dir = ExportDir::new("export_png");
+        let map = vec![
+            vec![explored(TileType::Grass, Content::None), explored(TileType::Sand, Content::Rock(1)), None],
+            vec![None, explored(TileType::Grass, Content::None)],
+        ];
+        let path = format!("{}.png", dir.prefix());
+        export_map_png(&map, &[(1, 1)], 4, &path).unwrap();
+
+        // The rows of the map are the rows of the image, the shorter rows are padded with unexplored tiles
+        let image = image::open(&path).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (12, 8));
+        assert_eq!(image.get_pixel(0, 0).0, [70, 160, 60, 255]);
+        assert_eq!(image.get_pixel(4, 0).0, [220, 205, 140, 255]);
+        assert_eq!(image.get_pixel(5, 1).0, [90, 90, 90, 255]);
+        assert_eq!(image.get_pixel(0, 4).0[3], 0);
+        assert_eq!(image.get_pixel(8, 4).0[3], 0);
+        assert_eq!(image.get_pixel(6, 6).0, ROBOT_COLOR);
+    }
+
+    #[test]
+    fn exports_the_map_as_obj() {
+        let dir = ExportDir::new("export_obj");
+        let map = vec![
+            vec![explored(TileType::Grass, Content::Rock(20)), explored(TileType::Sand, Content::None)],
+            vec![None, explored(TileType::Street, Content::Bin(5..10))],
+        ];
+        export_map_obj(&map, &[(0, 0), (0, 1)], &format!("{}.obj", dir.prefix())).unwrap();
+
+        // 3 tiles, a stack of 4 rocks, a bin with its gauge and the robot, with 4 vertices on each of the 6 faces
+        let obj = dir.read("run.obj");
+        let count = |prefix: &str| obj.lines().filter(|line| line.starts_with(prefix)).count();
+        assert_eq!(count("v "), 10 * 24 + 2);
+        assert_eq!(count("vt "), 10 * 24);
+        assert_eq!(count("f "), 10 * 6);
+        assert_eq!(count("l "), 1);
+        assert!(obj.lines().any(|line| line == "mtllib run.mtl"));
+
+        let mtl = dir.read("run.mtl");
+        let materials = mtl.lines().filter_map(|line| line.strip_prefix("newmtl ")).collect::<Vec<_>>();
+        assert_eq!(materials, ["grass", "sand", "street", "rock", "bin", "fill_gauge", "robot_path", "robot"]);
+        assert!(mtl.contains("map_Kd run_textures/cobblestone.png\n"));
+        assert!(dir.0.join("run_textures").join("cobblestone.png").is_file());
+    }
+}
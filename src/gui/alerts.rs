@@ -0,0 +1,444 @@
+use std::collections::{BTreeSet, VecDeque};
+
+use macroquad::prelude::*;
+use robotics_lib::world::environmental_conditions::WeatherType;
+
+use crate::gui::ui::UIProps;
+use crate::world_info::{content_name, weather_name};
+
+const TOAST_DURATION: f64 = 5.0;
+const TOAST_FADE: f64 = 1.0;
+const MAX_TOASTS: usize = 5;
+const TOAST_SIZE: Vec2 = vec2(420.0, 36.0);
+const TOAST_TOP: f32 = 110.0;
+
+/// Events of the focused robot that show a notification, set with
+/// [`VisualizerBuilder::alerts`](crate::VisualizerBuilder::alerts).
+///
+/// `None` or `false` disable an alert.
+///
+/// ```ignore
+/// let alerts = AlertSettings {
+///     score_change: Some(10.0),
+///     pause_on_alert: true,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone)]
+pub struct AlertSettings {
+    /// The energy went below this fraction of the maximum energy, the energy bar of the HUD turns red below it too
+    pub low_energy: Option<f32>,
+    /// The robot didn't move for this number of ticks
+    pub idle_ticks: Option<usize>,
+    /// The score changed by more than this amount in a single tick
+    pub score_change: Option<f32>,
+    /// A content never seen before is in the explored map
+    pub new_content: bool,
+    pub weather_change: bool,
+    /// The robot received `Event::Terminated`, it must forward its events with `Channel::send_event`
+    pub terminated: bool,
+    /// Pauses the game ticks when an alert is shown, resume from the console
+    pub pause_on_alert: bool,
+}
+
+impl Default for AlertSettings {
+    fn default() -> Self {
+        Self {
+            low_energy: Some(0.2),
+            idle_ticks: Some(20),
+            score_change: None,
+            new_content: true,
+            weather_change: true,
+            terminated: true,
+            pause_on_alert: false,
+        }
+    }
+}
+
+impl AlertSettings {
+    /// False if the low energy alert is disabled
+    pub(crate) fn is_energy_low(&self, energy: usize, max_energy: usize) -> bool {
+        self.low_energy.map_or(false, |fraction| (energy as f32) < max_energy as f32 * fraction)
+    }
+}
+
+struct Toast {
+    text: String,
+    created: f64,
+}
+
+/// What the alerts know about the focused robot, reset when the focus moves to another robot
+#[derive(Default)]
+struct WatchedRobot {
+    index: usize,
+    tick: usize,
+    coordinates: (usize, usize),
+    idle_ticks: usize,
+    score: f32,
+    weather_condition: Option<WeatherType>,
+    contents: BTreeSet<&'static str>,
+    low_energy: bool,
+    terminated: bool,
+}
+
+/// Checks the alert triggers at every tick of the focused robot and shows the notifications
+pub(super) struct Alerts {
+    settings: AlertSettings,
+    toasts: VecDeque<Toast>,
+    watched: Option<WatchedRobot>,
+}
+
+impl Alerts {
+    pub(super) fn new(settings: AlertSettings) -> Self {
+        Self {
+            settings,
+            toasts: VecDeque::new(),
+            watched: None,
+        }
+    }
+
+    pub(super) fn is_energy_low(&self, energy: usize, max_energy: usize) -> bool {
+        self.settings.is_energy_low(energy, max_energy)
+    }
+
+    fn explored_contents(props: &UIProps) -> BTreeSet<&'static str> {
+        props.explored_world_map.iter()
+            .flatten()
+            .flatten()
+            .map(|tile| content_name(&tile.content))
+            .filter(|name| *name != "None")
+            .collect()
+    }
+
+    fn show(&mut self, text: String) {
+        if self.toasts.len() == MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+        self.toasts.push_back(Toast {
+            text,
+            created: get_time(),
+        });
+    }
+
+    /// Shows the notifications triggered since the last call, returns true if the run should be paused
+    pub(super) fn update(&mut self, props: &UIProps) -> bool {
+        let alerts = self.check(props);
+        let pause = self.settings.pause_on_alert && !alerts.is_empty();
+        for alert in alerts {
+            self.show(if pause { format!("{} (paused)", alert) } else { alert });
+        }
+        pause
+    }
+
+    /// Text of the alerts triggered since the last call
+    fn check(&mut self, props: &UIProps) -> Vec<String> {
+        // Nothing is known about the robot before its first game info, the state at that moment doesn't trigger anything
+        let watched = match self.watched.as_mut() {
+            Some(watched) if watched.index == props.selected_robot => watched,
+            _ if props.ticks == 0 => return Vec::new(),
+            _ => {
+                self.watched = Some(WatchedRobot {
+                    index: props.selected_robot,
+                    tick: props.ticks,
+                    coordinates: props.robot_coordinates,
+                    score: props.robot_score,
+                    weather_condition: Some(props.weather_condition),
+                    contents: Self::explored_contents(props),
+                    low_energy: self.settings.is_energy_low(props.robot_energy, props.robot_max_energy),
+                    terminated: props.terminated,
+                    ..Default::default()
+                });
+                return Vec::new();
+            }
+        };
+
+        let mut alerts = Vec::new();
+
+        if self.settings.weather_change && watched.weather_condition != Some(props.weather_condition) {
+            alerts.push(format!("Weather changed to {}", weather_name(props.weather_condition)));
+        }
+        watched.weather_condition = Some(props.weather_condition);
+
+        if self.settings.terminated && props.terminated && !watched.terminated {
+            alerts.push("The robot terminated".to_string());
+        }
+        watched.terminated = props.terminated;
+
+        if props.ticks != watched.tick {
+            let ticks = props.ticks.saturating_sub(watched.tick);
+            watched.tick = props.ticks;
+
+            let low_energy = self.settings.is_energy_low(props.robot_energy, props.robot_max_energy);
+            if low_energy && !watched.low_energy {
+                alerts.push(format!("Low energy: {}/{}", props.robot_energy, props.robot_max_energy));
+            }
+            watched.low_energy = low_energy;
+
+            if watched.coordinates == props.robot_coordinates {
+                watched.idle_ticks += ticks;
+                if self.settings.idle_ticks.map_or(false, |idle_ticks| watched.idle_ticks >= idle_ticks && watched.idle_ticks - ticks < idle_ticks) {
+                    alerts.push(format!("The robot didn't move for {} ticks", watched.idle_ticks));
+                }
+            } else {
+                watched.coordinates = props.robot_coordinates;
+                watched.idle_ticks = 0;
+            }
+
+            let score_change = props.robot_score - watched.score;
+            if self.settings.score_change.map_or(false, |threshold| score_change.abs() > threshold) {
+                alerts.push(format!("Score changed by {:+} to {}", score_change, props.robot_score));
+            }
+            watched.score = props.robot_score;
+
+            if self.settings.new_content {
+                for content in Self::explored_contents(props) {
+                    if watched.contents.insert(content) {
+                        alerts.push(format!("Discovered the first {}", content));
+                    }
+                }
+            }
+        }
+
+        alerts
+    }
+
+    /// Draws the notifications under the top of the screen, the newest one at the top
+    pub(super) fn render(&mut self, scale: f32) {
+        let now = get_time();
+        self.toasts.retain(|toast| now - toast.created < TOAST_DURATION);
+
+        let size = TOAST_SIZE * scale;
+        let x = (screen_width() - size.x) / 2.0;
+        for (index, toast) in self.toasts.iter().rev().enumerate() {
+            let alpha = ((TOAST_DURATION - (now - toast.created)) / TOAST_FADE).min(1.0) as f32;
+            let y = (TOAST_TOP + index as f32 * (TOAST_SIZE.y + 6.0)) * scale;
+
+            draw_rectangle(x, y, size.x, size.y, Color::new(0.1, 0.1, 0.1, 0.8 * alpha));
+            draw_rectangle_lines(x, y, size.x, size.y, 2.0 * scale, Color::new(1.0, 0.8, 0.0, alpha));
+            draw_text(&toast.text, x + 10.0 * scale, y + size.y * 0.65, 22.0 * scale, Color::new(1.0, 1.0, 1.0, alpha));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use robotics_lib::world::environmental_conditions::WeatherType;
+    use robotics_lib::world::tile::{Content, TileType};
+
+    use super::{AlertSettings, Alerts};
+    use crate::channel::ChannelData;
+    use crate::gui::ui::UIProps;
+    use crate::test_fixtures::explored;
+
+    fn disabled() -> AlertSettings {
+        AlertSettings {
+            low_energy: None,
+            idle_ticks: None,
+            score_change: None,
+            new_content: false,
+            weather_change: false,
+            terminated: false,
+            pause_on_alert: false,
+        }
+    }
+
+    /// A robot that already sent its first game info
+    fn robot() -> ChannelData {
+        ChannelData {
+            explored_world_map: vec![vec![explored(TileType::Grass, Content::None); 3]; 3],
+            robot_energy: 1000,
+            robot_max_energy: 1000,
+            ticks: 1,
+            ..Default::default()
+        }
+    }
+
+    fn props(data: &ChannelData, selected_robot: usize) -> UIProps<'_> {
+        UIProps {
+            explored_world_map: &data.explored_world_map,
+            discoverable_tiles: data.discoverable_tiles,
+            robot_coordinates: data.robot_coordinates,
+            robot_path: &data.robot_path,
+            robot_energy: data.robot_energy,
+            robot_max_energy: data.robot_max_energy,
+            robot_backpack_contents: &data.robot_backpack_contents,
+            robot_backpack_size: data.robot_backpack_size,
+            robot_score: data.robot_score,
+            time_of_day: data.time_of_day,
+            time_of_day_string: data.time_of_day_string.clone(),
+            weather_condition: data.weather_condition,
+            robots: &[],
+            selected_robot,
+            comparison: false,
+            event_log: &data.event_log,
+            manual_control_available: false,
+            manual_mode: false,
+            debug: &data.debug,
+            ticks: data.ticks,
+            history: &data.history,
+            custom_values: &data.custom_values,
+            backpack_changes: &data.backpack_changes,
+            content_icons: HashMap::new(),
+            terminated: data.terminated,
+        }
+    }
+
+    /// Alerts started on the current state of the robot
+    fn watch(settings: AlertSettings, data: &ChannelData) -> Alerts {
+        let mut alerts = Alerts::new(settings);
+        assert!(alerts.check(&props(data, 0)).is_empty());
+        alerts
+    }
+
+    #[test]
+    fn nothing_triggers_before_and_at_the_first_game_info() {
+        let mut data = ChannelData {
+            robot_energy: 0,
+            terminated: true,
+            ..Default::default()
+        };
+        let mut alerts = Alerts::new(AlertSettings::default());
+        assert!(alerts.check(&props(&data, 0)).is_empty());
+
+        data.ticks = 1;
+        data.explored_world_map = vec![vec![explored(TileType::Grass, Content::Tree(2))]];
+        data.weather_condition = WeatherType::Rainy;
+        assert!(alerts.check(&props(&data, 0)).is_empty());
+    }
+
+    #[test]
+    fn low_energy_triggers_once_below_the_threshold() {
+        let mut data = robot();
+        let mut alerts = watch(AlertSettings { low_energy: Some(0.2), ..disabled() }, &data);
+
+        data.ticks += 1;
+        data.robot_energy = 200;
+        assert!(alerts.check(&props(&data, 0)).is_empty());
+
+        data.ticks += 1;
+        data.robot_energy = 150;
+        assert_eq!(alerts.check(&props(&data, 0)), vec!["Low energy: 150/1000"]);
+
+        data.ticks += 1;
+        data.robot_energy = 100;
+        assert!(alerts.check(&props(&data, 0)).is_empty());
+
+        data.ticks += 1;
+        data.robot_energy = 500;
+        assert!(alerts.check(&props(&data, 0)).is_empty());
+
+        data.ticks += 1;
+        data.robot_energy = 50;
+        assert_eq!(alerts.check(&props(&data, 0)), vec!["Low energy: 50/1000"]);
+    }
+
+    #[test]
+    fn idle_ticks_are_counted_until_the_robot_moves() {
+        let mut data = robot();
+        let mut alerts = watch(AlertSettings { idle_ticks: Some(3), ..disabled() }, &data);
+
+        for _ in 0..2 {
+            data.ticks += 1;
+            assert!(alerts.check(&props(&data, 0)).is_empty());
+        }
+        data.ticks += 1;
+        assert_eq!(alerts.check(&props(&data, 0)), vec!["The robot didn't move for 3 ticks"]);
+        data.ticks += 1;
+        assert!(alerts.check(&props(&data, 0)).is_empty());
+
+        data.ticks += 1;
+        data.robot_coordinates = (0, 1);
+        assert!(alerts.check(&props(&data, 0)).is_empty());
+
+        // Several ticks can pass between two frames
+        data.ticks += 5;
+        assert_eq!(alerts.check(&props(&data, 0)), vec!["The robot didn't move for 5 ticks"]);
+    }
+
+    #[test]
+    fn idle_ticks_are_not_counted_between_frames_without_ticks() {
+        let mut data = robot();
+        let mut alerts = watch(AlertSettings { idle_ticks: Some(2), ..disabled() }, &data);
+
+        data.ticks += 1;
+        for _ in 0..5 {
+            assert!(alerts.check(&props(&data, 0)).is_empty());
+        }
+        data.ticks += 1;
+        assert_eq!(alerts.check(&props(&data, 0)), vec!["The robot didn't move for 2 ticks"]);
+    }
+
+    #[test]
+    fn score_changes_above_the_threshold_trigger() {
+        let mut data = robot();
+        let mut alerts = watch(AlertSettings { score_change: Some(10.0), ..disabled() }, &data);
+
+        data.ticks += 1;
+        data.robot_score = 10.0;
+        assert!(alerts.check(&props(&data, 0)).is_empty());
+
+        data.ticks += 1;
+        data.robot_score = 25.0;
+        assert_eq!(alerts.check(&props(&data, 0)), vec!["Score changed by +15 to 25"]);
+    }
+
+    #[test]
+    fn only_contents_never_seen_trigger() {
+        let mut data = robot();
+        data.explored_world_map[0][0] = explored(TileType::Grass, Content::Rock(1));
+        let mut alerts = watch(AlertSettings { new_content: true, ..disabled() }, &data);
+
+        data.ticks += 1;
+        data.explored_world_map[0][1] = explored(TileType::Grass, Content::Rock(3));
+        data.explored_world_map[2][2] = explored(TileType::Grass, Content::Coin(1));
+        assert_eq!(alerts.check(&props(&data, 0)), vec!["Discovered the first Coin"]);
+
+        data.ticks += 1;
+        data.explored_world_map[0][0] = explored(TileType::Grass, Content::None);
+        data.explored_world_map[2][1] = explored(TileType::Grass, Content::Coin(2));
+        assert!(alerts.check(&props(&data, 0)).is_empty());
+    }
+
+    #[test]
+    fn weather_and_termination_trigger_without_a_tick() {
+        let mut data = robot();
+        let mut alerts = watch(AlertSettings { weather_change: true, terminated: true, ..disabled() }, &data);
+
+        data.weather_condition = WeatherType::Rainy;
+        assert_eq!(alerts.check(&props(&data, 0)), vec!["Weather changed to Rainy"]);
+
+        data.terminated = true;
+        assert_eq!(alerts.check(&props(&data, 0)), vec!["The robot terminated"]);
+        assert!(alerts.check(&props(&data, 0)).is_empty());
+    }
+
+    #[test]
+    fn disabled_alerts_never_trigger() {
+        let mut data = robot();
+        let mut alerts = watch(disabled(), &data);
+
+        data.ticks += 30;
+        data.robot_energy = 0;
+        data.robot_score = 1000.0;
+        data.weather_condition = WeatherType::Foggy;
+        data.explored_world_map[1][1] = explored(TileType::Grass, Content::Fish(1));
+        data.terminated = true;
+        assert!(alerts.check(&props(&data, 0)).is_empty());
+    }
+
+    #[test]
+    fn focusing_another_robot_starts_over() {
+        let mut data = robot();
+        let mut alerts = watch(AlertSettings::default(), &data);
+
+        data.robot_energy = 10;
+        data.weather_condition = WeatherType::Rainy;
+        data.explored_world_map[1][1] = explored(TileType::Grass, Content::Fish(1));
+        assert!(alerts.check(&props(&data, 1)).is_empty());
+
+        data.ticks += 1;
+        assert!(alerts.check(&props(&data, 1)).is_empty());
+    }
+}
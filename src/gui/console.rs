@@ -0,0 +1,200 @@
+use macroquad::hash;
+use macroquad::prelude::*;
+use macroquad::ui::{root_ui, widgets};
+
+const CONSOLE_HEIGHT: f32 = 300.0;
+const CONSOLE_HISTORY_SIZE: usize = 200;
+
+const HELP: [&str; 13] = [
+    "pause / resume - stop or restart the game ticks",
+    "step <ticks> - run the given number of ticks",
+    "speed <seconds> - set the game tick interval",
+    "goto <x> <y> - move the camera to a tile",
+    "find <content> - list the discovered tiles with that content",
+    "export map <file.png> - save the explored map as an image",
+    "export model <file.obj> - save the explored map as a 3D model",
+    "export data <prefix> - save the run as <prefix>_run.json, <prefix>_metrics.csv and <prefix>_map.csv",
+    "toggle grid - show or hide the grid",
+    "hud scale <scale> - resize the windows and the text of the HUD, 1 is the default size",
+    "hud reset - move the windows back to their default position",
+    "clear - clear the console",
+    "help - show this message",
+];
+
+pub(super) enum ConsoleCommand {
+    Pause,
+    Resume,
+    Step(usize),
+    Speed(f32),
+    Goto(usize, usize),
+    Find(String),
+    ExportMap(String),
+    ExportModel(String),
+    ExportData(String),
+    ToggleGrid,
+    HudScale(f32),
+    HudReset,
+}
+
+impl ConsoleCommand {
+    fn parse(line: &str) -> Result<Self, String> {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let number = |word: Option<&&str>, name: &str| -> Result<f32, String> {
+            word.ok_or(format!("missing {}", name))?
+                .parse::<f32>()
+                .map_err(|_| format!("{} must be a number", name))
+        };
+
+        match words.as_slice() {
+            ["pause"] => Ok(ConsoleCommand::Pause),
+            ["resume"] => Ok(ConsoleCommand::Resume),
+            ["step", rest @ ..] => Ok(ConsoleCommand::Step(number(rest.first(), "ticks")?.max(0.0) as usize)),
+            ["speed", rest @ ..] => Ok(ConsoleCommand::Speed(number(rest.first(), "seconds")?.clamp(0.0, 5.0))),
+            ["goto", rest @ ..] => Ok(ConsoleCommand::Goto(
+                number(rest.first(), "x")?.max(0.0) as usize,
+                number(rest.get(1), "y")?.max(0.0) as usize
+            )),
+            ["find", content] => Ok(ConsoleCommand::Find(content.to_lowercase())),
+            ["export", "map", path] => Ok(ConsoleCommand::ExportMap(path.to_string())),
+            ["export", "model", path] => Ok(ConsoleCommand::ExportModel(path.to_string())),
+            ["export", "data", prefix] => Ok(ConsoleCommand::ExportData(prefix.to_string())),
+            ["toggle", "grid"] => Ok(ConsoleCommand::ToggleGrid),
+            ["hud", "scale", rest @ ..] => Ok(ConsoleCommand::HudScale(number(rest.first(), "scale")?)),
+            ["hud", "reset"] => Ok(ConsoleCommand::HudReset),
+            [] => Err(String::new()),
+            _ => Err(format!("unknown command \"{}\", type help for the list of commands", line.trim())),
+        }
+    }
+}
+
+/// Drop-down console, opened with the `toggle_console` key
+#[derive(Default)]
+pub(super) struct Console {
+    open: bool,
+    /// The console was opened in this frame
+    opening: bool,
+    input: String,
+    output: Vec<String>,
+}
+
+impl Console {
+    pub(super) fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub(super) fn toggle(&mut self) {
+        self.open = !self.open;
+        self.opening = self.open;
+        self.input.clear();
+    }
+
+    pub(super) fn print(&mut self, line: impl Into<String>) {
+        if self.output.len() == CONSOLE_HISTORY_SIZE {
+            self.output.remove(0);
+        }
+        self.output.push(line.into());
+    }
+
+    fn submit(&mut self) -> Option<ConsoleCommand> {
+        let line = std::mem::take(&mut self.input);
+        self.print(format!("> {}", line));
+
+        match line.trim() {
+            "help" => {
+                HELP.iter().for_each(|line| self.print(*line));
+                None
+            }
+            "clear" => {
+                self.output.clear();
+                None
+            }
+            _ => match ConsoleCommand::parse(&line) {
+                Ok(command) => Some(command),
+                Err(error) => {
+                    if !error.is_empty() {
+                        self.print(error);
+                    }
+                    None
+                }
+            },
+        }
+    }
+
+    /// Draws the console and returns the command submitted in this frame, if any
+    pub(super) fn render(&mut self) -> Option<ConsoleCommand> {
+        if !self.open {
+            return None;
+        }
+
+        let input_id = hash!("console_input");
+        widgets::Window::new(
+            hash!("console_window"),
+            vec2(0.0, 0.0),
+            vec2(screen_width(), CONSOLE_HEIGHT)
+        )
+        .label("Console")
+        .titlebar(true)
+        .movable(false)
+        .ui(&mut *root_ui(), |ui| {
+            widgets::Group::new(hash!("console_output"), vec2(screen_width() - 10.0, CONSOLE_HEIGHT - 70.0))
+            .ui(ui, |ui| {
+                for line in self.output.iter() {
+                    ui.label(None, line);
+                }
+                ui.scroll_here();
+            });
+            ui.input_text(input_id, "", &mut self.input);
+        });
+        root_ui().set_input_focus(input_id);
+
+        // The character of the key that opens the console shouldn't end up in the command, whatever the key is
+        if self.opening {
+            self.opening = false;
+            self.input.clear();
+        }
+
+        if is_key_pressed(KeyCode::Enter) {
+            self.submit()
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConsoleCommand;
+
+    #[test]
+    fn parses_the_commands() {
+        assert!(matches!(ConsoleCommand::parse("pause"), Ok(ConsoleCommand::Pause)));
+        assert!(matches!(ConsoleCommand::parse("  resume  "), Ok(ConsoleCommand::Resume)));
+        assert!(matches!(ConsoleCommand::parse("step 10"), Ok(ConsoleCommand::Step(10))));
+        assert!(matches!(ConsoleCommand::parse("speed 0.5"), Ok(ConsoleCommand::Speed(seconds)) if seconds == 0.5));
+        assert!(matches!(ConsoleCommand::parse("goto 3 7"), Ok(ConsoleCommand::Goto(3, 7))));
+        assert!(matches!(ConsoleCommand::parse("find Rock"), Ok(ConsoleCommand::Find(content)) if content == "rock"));
+        assert!(matches!(ConsoleCommand::parse("export map map.png"), Ok(ConsoleCommand::ExportMap(path)) if path == "map.png"));
+        assert!(matches!(ConsoleCommand::parse("export model map.obj"), Ok(ConsoleCommand::ExportModel(path)) if path == "map.obj"));
+        assert!(matches!(ConsoleCommand::parse("export data run"), Ok(ConsoleCommand::ExportData(prefix)) if prefix == "run"));
+        assert!(matches!(ConsoleCommand::parse("toggle grid"), Ok(ConsoleCommand::ToggleGrid)));
+        assert!(matches!(ConsoleCommand::parse("hud scale 1.5"), Ok(ConsoleCommand::HudScale(scale)) if scale == 1.5));
+        assert!(matches!(ConsoleCommand::parse("hud reset"), Ok(ConsoleCommand::HudReset)));
+    }
+
+    #[test]
+    fn clamps_the_numbers() {
+        assert!(matches!(ConsoleCommand::parse("step -4"), Ok(ConsoleCommand::Step(0))));
+        assert!(matches!(ConsoleCommand::parse("speed 60"), Ok(ConsoleCommand::Speed(seconds)) if seconds == 5.0));
+        assert!(matches!(ConsoleCommand::parse("goto -1 2"), Ok(ConsoleCommand::Goto(0, 2))));
+    }
+
+    #[test]
+    fn reports_the_errors() {
+        assert!(matches!(ConsoleCommand::parse(""), Err(error) if error.is_empty()));
+        assert!(matches!(ConsoleCommand::parse("step"), Err(error) if error == "missing ticks"));
+        assert!(matches!(ConsoleCommand::parse("speed fast"), Err(error) if error == "seconds must be a number"));
+        assert!(matches!(ConsoleCommand::parse("goto 3"), Err(error) if error == "missing y"));
+        assert!(matches!(ConsoleCommand::parse("find"), Err(error) if error.starts_with("unknown command \"find\"")));
+        assert!(matches!(ConsoleCommand::parse("jump 3"), Err(error) if error == "unknown command \"jump 3\", type help for the list of commands"));
+    }
+}
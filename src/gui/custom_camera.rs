@@ -1,5 +1,5 @@
 use macroquad::input::{mouse_position, is_key_down};
-use macroquad::{camera::Camera3D, math::{vec3, Vec2, Vec3}};
+use macroquad::{camera::Camera3D, math::{vec3, Rect, Vec2, Vec3}};
 use crate::gui::keyboard_controls::KeyboardControls;
 
 pub(super) struct CustomCamera {
@@ -15,6 +15,24 @@ pub(super) struct CustomCamera {
     keyboard_controls: KeyboardControls
 }
 
+/// How the camera moves at the start and when the robot moves
+#[derive(Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    /// Moved only with the keyboard and the mouse
+    Free,
+    /// Moves near the focused robot every time it changes position
+    FollowRobot,
+}
+
+impl CameraMode {
+    pub(super) fn next(self) -> Self {
+        match self {
+            CameraMode::Free => CameraMode::FollowRobot,
+            CameraMode::FollowRobot => CameraMode::Free,
+        }
+    }
+}
+
 enum Direction {
     Forward,
     Backward,
@@ -25,14 +43,56 @@ enum Direction {
 }
 
 impl CustomCamera {
+    pub(super) fn new(position: Vec3, keyboard_controls: KeyboardControls) -> Self {
+        let mut camera = Self {
+            actual_camera: Default::default(),
+            position,
+            up: vec3(0.0, 1.0, 0.0),
+            front: Default::default(),
+            move_speed: 0.5,
+            look_speed: 0.001,
+            pitch: 0.0,
+            yaw: 0.0,
+            mouse_position: Default::default(),
+            keyboard_controls,
+        };
+        camera.update();
+        camera
+    }
+
     pub(super) fn get_actual_camera(&self) -> &Camera3D {
         &self.actual_camera
     }
 
+    /// Copy of the camera that only draws on a part of the screen, used for split screen
+    pub(super) fn get_viewport_camera(&self, viewport: Rect) -> Camera3D {
+        Camera3D {
+            position: self.actual_camera.position,
+            target: self.actual_camera.target,
+            up: self.actual_camera.up,
+            aspect: Some(viewport.w / viewport.h),
+            viewport: Some((viewport.x as i32, viewport.y as i32, viewport.w as i32, viewport.h as i32)),
+            ..Default::default()
+        }
+    }
+
+    pub(super) fn get_position(&self) -> Vec3 {
+        self.position
+    }
+
     pub(super) fn get_front(&self) -> Vec3 {
         self.front
     }
 
+    /// Moves the camera near the given world position and points it there
+    pub(super) fn look_at(&mut self, target: Vec3) {
+        self.position = target + vec3(-8.0, 12.0, -8.0);
+        let direction = (target - self.position).normalize();
+        self.pitch = direction.y.asin().clamp(-1.5, 1.5);
+        self.yaw = direction.z.atan2(direction.x);
+        self.update();
+    }
+
     fn update_position(&mut self, direction: Direction) {
         let front = self.front * self.move_speed;
         let right = self.front.cross(self.up).normalize() * self.move_speed;
@@ -98,25 +158,14 @@ impl CustomCamera {
         self.update_orientation(mouse_position().into())
     }
 
-    pub(super) fn handle_input(&mut self) {
+    /// The mouse turns the camera only while it's grabbed, so it can be used on the HUD
+    pub(super) fn handle_input(&mut self, mouse_grabbed: bool) {
         self.handle_keys();
-        self.handle_mouse();
-    }
-}
-
-impl Default for CustomCamera {
-    fn default() -> Self {
-        Self {
-            actual_camera: Default::default(),
-            position: vec3(-10.0, 100.0, -10.0),
-            up: vec3(0.0, 1.0, 0.0),
-            front: Default::default(),
-            move_speed: 0.5,
-            look_speed: 0.001,
-            pitch: 0.0,
-            yaw: 0.0,
-            mouse_position: Default::default(),
-            keyboard_controls: Default::default(),
+        if mouse_grabbed {
+            self.handle_mouse();
+        } else {
+            // The camera doesn't jump when the mouse is grabbed again
+            self.mouse_position = mouse_position().into();
         }
     }
-}
\ No newline at end of file
+}
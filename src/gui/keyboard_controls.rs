@@ -1,18 +1,41 @@
 use macroquad::input::KeyCode;
 
-pub(super) struct KeyboardControls {
-    pub(super) exit: KeyCode,
-    pub(super) toggle_free_mouse: KeyCode,
-    pub(super) move_forward: KeyCode,
-    pub(super) move_backward: KeyCode,
-    pub(super) move_left: KeyCode,
-    pub(super) move_right: KeyCode,
-    pub(super) move_up: KeyCode,
-    pub(super) move_down: KeyCode,
-    pub(super) toggle_tile_info: KeyCode,
-    pub(super) toggle_help: KeyCode,
-    pub(super) toggle_statistics: KeyCode,
-    pub(super) toggle_hud: KeyCode,
+/// Key bindings of the visualizer, change the ones you need starting from the default ones
+#[derive(Clone)]
+pub struct KeyboardControls {
+    pub exit: KeyCode,
+    pub toggle_free_mouse: KeyCode,
+    pub move_forward: KeyCode,
+    pub move_backward: KeyCode,
+    pub move_left: KeyCode,
+    pub move_right: KeyCode,
+    pub move_up: KeyCode,
+    pub move_down: KeyCode,
+    pub toggle_tile_info: KeyCode,
+    pub toggle_help: KeyCode,
+    pub toggle_statistics: KeyCode,
+    pub toggle_hud: KeyCode,
+    pub toggle_teleports: KeyCode,
+    pub next_robot: KeyCode,
+    pub cycle_world_view: KeyCode,
+    pub toggle_manual_mode: KeyCode,
+    pub robot_up: KeyCode,
+    pub robot_down: KeyCode,
+    pub robot_left: KeyCode,
+    pub robot_right: KeyCode,
+    pub robot_destroy: KeyCode,
+    pub robot_put: KeyCode,
+    pub robot_next_put_content: KeyCode,
+    pub robot_teleport: KeyCode,
+    pub toggle_event_log: KeyCode,
+    pub toggle_console: KeyCode,
+    pub toggle_search: KeyCode,
+    pub export_map: KeyCode,
+    pub export_data: KeyCode,
+    pub export_model: KeyCode,
+    pub cycle_camera_mode: KeyCode,
+    pub toggle_debug: KeyCode,
+    pub toggle_telemetry: KeyCode,
     //pub(super) take_screenshot: KeyCode
 }
 
@@ -31,6 +54,27 @@ impl Default for KeyboardControls {
             toggle_help: KeyCode::H,
             toggle_statistics: KeyCode::F3,
             toggle_hud: KeyCode::C,
+            toggle_teleports: KeyCode::T,
+            next_robot: KeyCode::Tab,
+            cycle_world_view: KeyCode::V,
+            toggle_manual_mode: KeyCode::M,
+            robot_up: KeyCode::Up,
+            robot_down: KeyCode::Down,
+            robot_left: KeyCode::Left,
+            robot_right: KeyCode::Right,
+            robot_destroy: KeyCode::X,
+            robot_put: KeyCode::P,
+            robot_next_put_content: KeyCode::O,
+            robot_teleport: KeyCode::Z,
+            toggle_event_log: KeyCode::L,
+            toggle_console: KeyCode::GraveAccent,
+            toggle_search: KeyCode::F,
+            export_map: KeyCode::F4,
+            export_data: KeyCode::F5,
+            export_model: KeyCode::F6,
+            cycle_camera_mode: KeyCode::K,
+            toggle_debug: KeyCode::B,
+            toggle_telemetry: KeyCode::J,
             //take_screenshot: KeyCode::F2
         }
     }
@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use macroquad::prelude::*;
+use macroquad::ui::{root_ui, Skin};
+use serde::{Deserialize, Serialize};
+
+const BASE_FONT_SIZE: f32 = 16.0;
+const BASE_TITLE_HEIGHT: f32 = 14.0;
+const MIN_HUD_SCALE: f32 = 0.5;
+const MAX_HUD_SCALE: f32 = 3.0;
+
+/// Side of the screen a window sticks to when the screen is resized
+#[derive(Clone, Copy)]
+pub(super) enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Center,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    fn position(self, screen: Vec2, size: Vec2) -> Vec2 {
+        let free_space = screen - size;
+        match self {
+            Anchor::TopLeft => vec2(0.0, 0.0),
+            Anchor::Top => vec2(free_space.x / 2.0, 0.0),
+            Anchor::TopRight => vec2(free_space.x, 0.0),
+            Anchor::Center => free_space / 2.0,
+            Anchor::BottomLeft => vec2(0.0, free_space.y),
+            Anchor::Bottom => vec2(free_space.x / 2.0, free_space.y),
+            Anchor::BottomRight => free_space,
+        }
+    }
+}
+
+/// Content of the layout file
+#[derive(Default, Serialize, Deserialize)]
+struct SavedLayout {
+    /// Distance of the windows moved by the user from their anchored position, in unscaled pixels
+    moved_windows: BTreeMap<String, (f32, f32)>,
+}
+
+/// Places the HUD windows every frame, so they follow the window resizes, the HUD scale and the user drags
+pub(super) struct HudLayout {
+    scale: f32,
+    file: Option<PathBuf>,
+    moved_windows: BTreeMap<String, Vec2>,
+    /// Windows placed in the last frame, the last one is drawn on top
+    placed_windows: Vec<(String, Rect)>,
+    /// Name of the window being dragged and mouse position in the last frame
+    dragged_window: Option<(String, Vec2)>,
+    skin_pushed: bool,
+}
+
+impl HudLayout {
+    /// The windows moved in a previous run are loaded from `file`, if it exists
+    pub(super) fn new(scale: f32, file: Option<PathBuf>) -> Self {
+        let saved = file.as_ref()
+            .and_then(|file| fs::read_to_string(file).ok())
+            .and_then(|json| serde_json::from_str::<SavedLayout>(&json).ok())
+            .unwrap_or_default();
+
+        let mut layout = Self {
+            scale: 1.0,
+            file,
+            moved_windows: saved.moved_windows.into_iter()
+                .map(|(name, (x, y))| (name, vec2(x, y)))
+                .collect(),
+            placed_windows: Vec::new(),
+            dragged_window: None,
+            skin_pushed: false,
+        };
+        layout.set_scale(scale);
+        layout
+    }
+
+    pub(super) fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Scales the windows and the fonts of the HUD, the scale is clamped between 0.5 and 3
+    pub(super) fn set_scale(&mut self, scale: f32) {
+        self.scale = scale.clamp(MIN_HUD_SCALE, MAX_HUD_SCALE);
+
+        if self.skin_pushed {
+            root_ui().pop_skin();
+            self.skin_pushed = false;
+        }
+        if self.scale != 1.0 {
+            let skin = Self::scaled_skin(self.scale);
+            root_ui().push_skin(&skin);
+            self.skin_pushed = true;
+        }
+    }
+
+    /// Default skin of macroquad with bigger or smaller text, the styles with a background image are kept as they are
+    fn scaled_skin(scale: f32) -> Skin {
+        let font_size = (BASE_FONT_SIZE * scale).round() as u16;
+        let margin = RectOffset::new(2.0 * scale, 2.0 * scale, 2.0 * scale, 2.0 * scale);
+        let ui = root_ui();
+
+        let label_style = ui.style_builder()
+            .font_size(font_size)
+            .margin(margin)
+            .text_color(BLACK)
+            .color_inactive(Color::from_rgba(0, 0, 0, 128))
+            .build();
+        let button_style = ui.style_builder()
+            .font_size(font_size)
+            .margin(margin)
+            .color(Color::from_rgba(204, 204, 204, 235))
+            .color_clicked(Color::from_rgba(187, 187, 187, 255))
+            .color_hovered(Color::from_rgba(170, 170, 170, 235))
+            .text_color(BLACK)
+            .build();
+        let window_titlebar_style = ui.style_builder()
+            .font_size(font_size)
+            .color(Color::from_rgba(68, 68, 68, 255))
+            .color_inactive(Color::from_rgba(102, 102, 102, 127))
+            .text_color(BLACK)
+            .build();
+        let checkbox_style = ui.style_builder()
+            .font_size(font_size)
+            .text_color(BLACK)
+            .color(Color::from_rgba(200, 200, 200, 255))
+            .color_hovered(Color::from_rgba(210, 210, 210, 255))
+            .color_clicked(Color::from_rgba(150, 150, 150, 255))
+            .color_selected(Color::from_rgba(128, 128, 128, 255))
+            .color_selected_hovered(Color::from_rgba(140, 140, 140, 255))
+            .build();
+        let editbox_style = ui.style_builder()
+            .font_size(font_size)
+            .text_color(BLACK)
+            .color_selected(Color::from_rgba(200, 200, 200, 255))
+            .build();
+
+        Skin {
+            label_style,
+            button_style,
+            window_titlebar_style,
+            checkbox_style,
+            editbox_style,
+            title_height: BASE_TITLE_HEIGHT * scale,
+            ..ui.default_skin()
+        }
+    }
+
+    /// Moves the window whose title bar is dragged with the mouse, call it once per frame before placing the windows.
+    ///
+    /// The new position is saved when the mouse button is released.
+    pub(super) fn handle_drag(&mut self, mouse_free: bool) -> Result<(), String> {
+        let placed_windows = std::mem::take(&mut self.placed_windows);
+        if !mouse_free {
+            self.dragged_window = None;
+            return Ok(());
+        }
+
+        let mouse = Vec2::from(mouse_position());
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let title_height = BASE_TITLE_HEIGHT * self.scale;
+            self.dragged_window = placed_windows.iter()
+                .rev()
+                .find(|(_, rect)| Rect::new(rect.x, rect.y, rect.w, title_height).contains(mouse))
+                .map(|(name, _)| (name.clone(), mouse));
+        }
+
+        let Some((name, last_mouse)) = self.dragged_window.take() else {
+            return Ok(());
+        };
+        if is_mouse_button_down(MouseButton::Left) {
+            *self.moved_windows.entry(name.clone()).or_insert(Vec2::ZERO) += (mouse - last_mouse) / self.scale;
+            self.dragged_window = Some((name, mouse));
+            Ok(())
+        } else {
+            self.save()
+        }
+    }
+
+    /// Position and size of a window in this frame.
+    ///
+    /// `offset` is the distance from the anchored position in unscaled pixels,
+    /// the window must not be movable by macroquad, or it would ignore the position after the first frame.
+    pub(super) fn place(&mut self, name: &str, anchor: Anchor, offset: Vec2, size: Vec2) -> (Vec2, Vec2) {
+        let screen = vec2(screen_width(), screen_height());
+        let size = (size * self.scale).min(screen);
+        let anchored = anchor.position(screen, size) + offset * self.scale;
+
+        let position = match self.moved_windows.get_mut(name) {
+            Some(moved) => {
+                let position = (anchored + *moved * self.scale).clamp(Vec2::ZERO, screen - size);
+                // A window dragged past the edge of the screen comes back as soon as it's dragged the other way
+                *moved = (position - anchored) / self.scale;
+                position
+            }
+            None => anchored.clamp(Vec2::ZERO, screen - size),
+        };
+
+        self.placed_windows.push((name.to_string(), Rect::new(position.x, position.y, size.x, size.y)));
+        (position, size)
+    }
+
+    /// Moves every window back to its anchored position
+    pub(super) fn reset(&mut self) -> Result<(), String> {
+        self.moved_windows.clear();
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+
+        let saved = SavedLayout {
+            moved_windows: self.moved_windows.iter()
+                .map(|(name, moved)| (name.clone(), (moved.x, moved.y)))
+                .collect(),
+        };
+        let json = serde_json::to_string_pretty(&saved).map_err(|error| format!("Error saving the HUD layout: {}", error))?;
+        fs::write(file, json).map_err(|error| format!("Error saving the HUD layout to {}: {}", file.display(), error))
+    }
+}
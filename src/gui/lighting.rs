@@ -0,0 +1,190 @@
+use macroquad::prelude::*;
+use robotics_lib::world::environmental_conditions::DayTime;
+
+pub(super) const TERRAIN_VERTEX_SHADER: &str = "#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}
+";
+
+pub(super) const TERRAIN_FRAGMENT_SHADER: &str = "#version 100
+precision mediump float;
+
+varying lowp vec4 color;
+varying lowp vec2 uv;
+
+uniform sampler2D Texture;
+uniform vec3 u_light_color;
+uniform vec3 u_ambient;
+uniform float u_emissive;
+uniform float u_time;
+
+void main() {
+    vec4 texel = texture2D(Texture, uv);
+    // The vertex color carries the per-face diffuse term in rgb and the fog visibility in alpha
+    vec3 lit = texel.rgb * (color.rgb * u_light_color + u_ambient);
+    float glow = u_emissive * (0.85 + 0.15 * sin(u_time * 3.0));
+    gl_FragColor = vec4(mix(lit, texel.rgb, clamp(glow, 0.0, 1.0)), texel.a * color.a);
+}
+";
+
+struct LightKeyframe {
+    hour: f32,
+    color: Vec3,
+    ambient: Vec3,
+}
+
+const NIGHT_COLOR: Vec3 = Vec3::new(0.15, 0.18, 0.35);
+const NIGHT_AMBIENT: Vec3 = Vec3::new(0.12, 0.12, 0.2);
+
+const KEYFRAMES: [LightKeyframe; 6] = [
+    LightKeyframe { hour: 0.0, color: NIGHT_COLOR, ambient: NIGHT_AMBIENT },
+    LightKeyframe { hour: 6.0, color: Vec3::new(0.9, 0.75, 0.6), ambient: Vec3::new(0.35, 0.33, 0.3) },
+    LightKeyframe { hour: 12.0, color: Vec3::new(1.0, 0.98, 0.92), ambient: Vec3::new(0.45, 0.45, 0.45) },
+    LightKeyframe { hour: 17.0, color: Vec3::new(1.0, 0.6, 0.35), ambient: Vec3::new(0.35, 0.28, 0.25) },
+    LightKeyframe { hour: 21.0, color: NIGHT_COLOR, ambient: NIGHT_AMBIENT },
+    LightKeyframe { hour: 24.0, color: NIGHT_COLOR, ambient: NIGHT_AMBIENT },
+];
+
+/// Directional light (sun or moon) used to shade the terrain
+#[derive(Clone, Copy)]
+pub(super) struct Lighting {
+    pub direction: Vec3,
+    pub color: Vec3,
+    pub ambient: Vec3,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self::at_hour(12.0)
+    }
+}
+
+impl Lighting {
+    fn parse_hour(time_of_day_string: &str) -> Option<f32> {
+        let (hours, minutes) = time_of_day_string.split_once(':')?;
+        let hours = hours.trim().parse::<f32>().ok()?;
+        let minutes = minutes.trim().parse::<f32>().ok()?;
+
+        Some((hours + minutes / 60.0).rem_euclid(24.0))
+    }
+
+    fn at_hour(hour: f32) -> Self {
+        let next = KEYFRAMES.iter().position(|keyframe| keyframe.hour > hour).unwrap_or(KEYFRAMES.len() - 1);
+        let (from, to) = (&KEYFRAMES[next.saturating_sub(1)], &KEYFRAMES[next]);
+        let t = if to.hour > from.hour { (hour - from.hour) / (to.hour - from.hour) } else { 0.0 };
+
+        // The sun rises at 6:00 and sets at 18:00, at night the moon lights from the opposite side
+        let angle = (hour - 6.0) / 24.0 * std::f32::consts::TAU;
+        let sun = vec3(angle.cos(), angle.sin(), 0.3).normalize();
+        let direction = if sun.y >= 0.0 { sun } else { -sun };
+
+        Self {
+            direction,
+            color: from.color.lerp(to.color, t),
+            ambient: from.ambient.lerp(to.ambient, t),
+        }
+    }
+
+    pub(super) fn new(time_of_day: DayTime, time_of_day_string: &str) -> Self {
+        let hour = Self::parse_hour(time_of_day_string).unwrap_or(match time_of_day {
+            DayTime::Morning => 9.0,
+            DayTime::Afternoon => 16.0,
+            DayTime::Night => 0.0,
+        });
+
+        Self::at_hour(hour)
+    }
+
+    /// Diffuse term for a face with the given normal
+    fn shade(&self, normal: Vec3) -> f32 {
+        normal.dot(self.direction).max(0.0)
+    }
+
+    fn shaded_color(&self, color: Color, normal: Vec3) -> Color {
+        let shade = self.shade(normal);
+        Color::new(color.r * shade, color.g * shade, color.b * shade, color.a)
+    }
+
+    /// Draws an axis aligned box starting from `offset`, shading every face by its orientation
+    pub(super) fn draw_block(&self, offset: Vec3, size: Vec3, texture: Option<&Texture2D>, color: Color) {
+        let (x, y, z) = (size.x * Vec3::X, size.y * Vec3::Y, size.z * Vec3::Z);
+
+        draw_affine_parallelogram(offset, x, z, texture, self.shaded_color(color, -Vec3::Y));
+        draw_affine_parallelogram(offset + y, x, z, texture, self.shaded_color(color, Vec3::Y));
+        draw_affine_parallelogram(offset, y, z, texture, self.shaded_color(color, -Vec3::X));
+        draw_affine_parallelogram(offset + x, y, z, texture, self.shaded_color(color, Vec3::X));
+        draw_affine_parallelogram(offset, x, y, texture, self.shaded_color(color, -Vec3::Z));
+        draw_affine_parallelogram(offset + z, x, y, texture, self.shaded_color(color, Vec3::Z));
+    }
+
+    /// Same as [`Lighting::draw_block`] but centered in `position`, like `draw_cube`
+    pub(super) fn draw_cube(&self, position: Vec3, size: Vec3, texture: Option<&Texture2D>, color: Color) {
+        self.draw_block(position - size / 2.0, size, texture, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use robotics_lib::world::environmental_conditions::DayTime;
+
+    use super::{Lighting, KEYFRAMES, NIGHT_AMBIENT, NIGHT_COLOR};
+
+    #[test]
+    fn parses_the_time_of_day() {
+        assert_eq!(Lighting::parse_hour("08:30"), Some(8.5));
+        assert_eq!(Lighting::parse_hour(" 17 : 15 "), Some(17.25));
+        assert_eq!(Lighting::parse_hour("24:00"), Some(0.0));
+        assert_eq!(Lighting::parse_hour("0830"), None);
+        assert_eq!(Lighting::parse_hour("08:xx"), None);
+        assert_eq!(Lighting::parse_hour(""), None);
+    }
+
+    #[test]
+    fn uses_the_keyframes_at_their_hour() {
+        for keyframe in KEYFRAMES.iter() {
+            let lighting = Lighting::at_hour(keyframe.hour);
+            assert!(lighting.color.abs_diff_eq(keyframe.color, 1e-5), "color at {}", keyframe.hour);
+            assert!(lighting.ambient.abs_diff_eq(keyframe.ambient, 1e-5), "ambient at {}", keyframe.hour);
+        }
+    }
+
+    #[test]
+    fn blends_the_keyframes_in_between() {
+        let lighting = Lighting::at_hour(9.0);
+
+        assert!(lighting.color.abs_diff_eq(KEYFRAMES[1].color.lerp(KEYFRAMES[2].color, 0.5), 1e-5));
+        assert!(lighting.ambient.abs_diff_eq(KEYFRAMES[1].ambient.lerp(KEYFRAMES[2].ambient, 0.5), 1e-5));
+    }
+
+    #[test]
+    fn lights_from_above_day_and_night() {
+        for hour in 0..24 {
+            let lighting = Lighting::at_hour(hour as f32);
+            assert!(lighting.direction.y >= 0.0, "light from below at {}", hour);
+            assert!((lighting.direction.length() - 1.0).abs() < 1e-5);
+        }
+        assert!(Lighting::at_hour(12.0).direction.y > 0.9);
+    }
+
+    #[test]
+    fn falls_back_to_the_day_time() {
+        let night = Lighting::new(DayTime::Night, "");
+
+        assert!(night.color.abs_diff_eq(NIGHT_COLOR, 1e-5));
+        assert!(night.ambient.abs_diff_eq(NIGHT_AMBIENT, 1e-5));
+        assert!(Lighting::new(DayTime::Morning, "12:00").color.abs_diff_eq(KEYFRAMES[2].color, 1e-5));
+    }
+}
@@ -1,94 +1,269 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
 
 use macroquad::prelude::*;
-use custom_camera::CustomCamera;
+use macroquad::ui::root_ui;
+use robotics_lib::world::tile::Tile;
+use custom_camera::{CameraMode, CustomCamera};
 use renderer::Renderer;
+use alerts::{AlertSettings, Alerts};
+use layout::HudLayout;
 use ui::UI;
+use weather_effects::WeatherEffects;
 
 use crate::channel::ChannelData;
-use renderer::RendererProps;
+use crate::world_info::content_name;
+use crate::plugin::{OlympusPlugin, RenderContext};
+use renderer::{RendererProps, RendererSettings};
 use ui::UIProps;
 
 use self::keyboard_controls::KeyboardControls;
 
-mod keyboard_controls;
-mod custom_camera;
+pub(crate) mod alerts;
+pub(crate) mod keyboard_controls;
+mod console;
+pub(crate) mod custom_camera;
+mod layout;
+mod lighting;
 mod renderer;
+mod search;
 mod ui;
+mod weather_effects;
+
+/// Snapshot of a registered robot, used to render a frame
+pub(super) struct RobotView<'a> {
+    pub name: &'a str,
+    pub color: Color,
+    pub data: &'a ChannelData,
+    pub ground_truth: &'a Vec<Vec<Tile>>,
+}
+
+/// Initial state of the GUI, set with the `VisualizerBuilder`
+pub(super) struct GUISettings {
+    pub show_hud: bool,
+    pub daylight_cycle: bool,
+    pub start_paused: bool,
+    pub camera_position: Vec3,
+    pub camera_mode: CameraMode,
+    pub keyboard_controls: KeyboardControls,
+    pub texture_pack: Option<PathBuf>,
+    pub hud_scale: f32,
+    pub hud_layout_file: Option<PathBuf>,
+    pub alerts: AlertSettings,
+}
 
 pub(super) struct GUI {
     camera: CustomCamera,
     renderer: Renderer,
     pub(super) ui: UI,
     keyboard_controls: KeyboardControls,
+    weather_effects: WeatherEffects,
     show_hud: bool,
+    focused_robot: usize,
+    split_screen: bool,
+    camera_mode: CameraMode,
+    followed_coordinates: Option<(usize, usize)>,
 }
 
 impl GUI {
-    pub(super) fn new(world_size: usize, tick_time: Rc<RefCell<f32>>) -> Self {
+    pub(super) fn new(tick_time: Rc<RefCell<f32>>, settings: GUISettings) -> Self {
         Self {
-            camera: Default::default(),
-            renderer: Renderer::new(world_size),
-            ui: UI::new(tick_time),
-            keyboard_controls: Default::default(),
-            show_hud: true,
+            camera: CustomCamera::new(settings.camera_position, settings.keyboard_controls.clone()),
+            renderer: Renderer::new(settings.texture_pack.as_deref()),
+            ui: UI::new(
+                tick_time,
+                settings.keyboard_controls.clone(),
+                settings.daylight_cycle,
+                settings.start_paused,
+                HudLayout::new(settings.hud_scale, settings.hud_layout_file),
+                Alerts::new(settings.alerts)
+            ),
+            keyboard_controls: settings.keyboard_controls,
+            weather_effects: Default::default(),
+            show_hud: settings.show_hud,
+            focused_robot: 0,
+            split_screen: false,
+            camera_mode: settings.camera_mode,
+            followed_coordinates: None,
         }
     }
 
+    pub(super) fn set_split_screen(&mut self, split_screen: bool) {
+        self.split_screen = split_screen;
+    }
+
     pub(super) fn handle_input(&mut self) {
+        self.ui.handle_input();
+        if self.ui.is_console_open() {
+            return; // Keys are typed in the console
+        }
+
         if is_key_pressed(self.keyboard_controls.toggle_hud) {
             self.show_hud = !self.show_hud;
         }
+        if is_key_pressed(self.keyboard_controls.cycle_camera_mode) {
+            self.camera_mode = self.camera_mode.next();
+            self.followed_coordinates = None;
+        }
 
-        self.camera.handle_input();
-        self.ui.handle_input();
+        self.camera.handle_input(self.ui.is_mouse_grabbed());
     }
     
-    fn update_camera(&mut self) {
-        self.camera.update();
-        set_camera(self.camera.get_actual_camera());
+    fn renderer_props<'a>(&self, robots: &'a [RobotView<'a>], selected: usize, show_other_robots: bool) -> RendererProps<'a> {
+        let data = robots[selected].data;
+
+        RendererProps { 
+            explored_world_map: &data.explored_world_map,
+            ground_truth: robots[selected].ground_truth,
+            robot_coordinates: data.robot_coordinates,
+            robot_color: robots[selected].color,
+            other_robots: robots.iter()
+                .enumerate()
+                .filter(|(index, _)| show_other_robots && *index != selected)
+                .map(|(_, robot)| (robot.data.robot_coordinates, robot.color))
+                .collect(),
+            time_of_day: data.time_of_day,
+            time_of_day_string: &data.time_of_day_string,
+            weather_condition: data.weather_condition,
+            // The search runs on the map of the focused robot, in split screen the other view has its own map
+            highlighted_tiles: if selected == self.focused_robot { self.ui.get_search_results() } else { Vec::new() },
+            debug: &data.debug,
+        }
     }
-    
-    fn render_game(&self, data: &ChannelData, camera_front: Vec3) {
+
+    fn render_game(&self, robots: &[RobotView], plugins: &[Box<dyn OlympusPlugin>], selected: usize, viewport: Option<Rect>) {
+        let data = robots[selected].data;
+        let viewport_camera;
+        let camera = match viewport {
+            Some(viewport) => {
+                viewport_camera = self.camera.get_viewport_camera(viewport);
+                &viewport_camera
+            }
+            None => self.camera.get_actual_camera(),
+        };
+        set_camera(camera);
+
         self.renderer.render(
-            RendererProps { 
-                explored_world_map: &data.explored_world_map,
-                robot_coordinates: data.robot_coordinates,
-                time_of_day: data.time_of_day
+            self.renderer_props(robots, selected, !self.split_screen),
+            RendererSettings {
+                daylight_cycle: self.ui.is_day_light_cycle_on(),
+                fog_of_war: self.ui.is_fog_of_war_on(),
+                world_view: self.ui.get_world_view(),
+                show_grid: self.ui.is_grid_on(),
+                // In split screen the first view clears the whole screen for both
+                clear_background: viewport.map_or(true, |viewport| viewport.x == 0.0),
             },
-            self.ui.is_day_light_cycle_on(),
-            camera_front
+            self.camera.get_position(),
+            self.camera.get_front()
         );
+
+        let context = RenderContext {
+            camera,
+            viewport: viewport.unwrap_or(Rect::new(0.0, 0.0, screen_width(), screen_height())),
+            data,
+        };
+        for plugin in plugins.iter() {
+            plugin.draw_3d(&context);
+        }
+
+        self.weather_effects.render(data.weather_condition);
+
+        if self.ui.are_content_labels_on() {
+            set_default_camera();
+            self.renderer.render_content_labels(
+                &self.renderer_props(robots, selected, !self.split_screen),
+                camera,
+                viewport.unwrap_or(Rect::new(0.0, 0.0, screen_width(), screen_height())),
+                20.0
+            );
+        }
+        if !data.debug.markers.is_empty() {
+            set_default_camera();
+            self.renderer.render_debug_labels(
+                &self.renderer_props(robots, selected, !self.split_screen),
+                camera,
+                viewport.unwrap_or(Rect::new(0.0, 0.0, screen_width(), screen_height()))
+            );
+        }
     }
     
-    fn render_ui(&mut self, data: &ChannelData) {
-        set_default_camera();
-        self.ui.render(
-            UIProps { 
-                explored_world_map: &data.explored_world_map,
-                robot_coordinates: data.robot_coordinates,
-                robot_energy: data.robot_energy,
-                robot_backpack_contents: &data.robot_backpack_contents,
-                robot_backpack_size: data.robot_backpack_size,
-                discoverable_tiles: data.discoverable_tiles,
-                robot_score: data.robot_score,
-                time_of_day: data.time_of_day,
-                time_of_day_string: data.time_of_day_string.clone(),
-                weather_condition: data.weather_condition
-            }
-        );
+    fn ui_props<'a>(&self, robots: &'a [RobotView<'a>], selected: usize) -> UIProps<'a> {
+        let data = robots[selected].data;
+        UIProps {
+            explored_world_map: &data.explored_world_map,
+            robot_coordinates: data.robot_coordinates,
+            robot_path: &data.robot_path,
+            robot_energy: data.robot_energy,
+            robot_max_energy: data.robot_max_energy,
+            robot_backpack_contents: &data.robot_backpack_contents,
+            robot_backpack_size: data.robot_backpack_size,
+            discoverable_tiles: data.discoverable_tiles,
+            robot_score: data.robot_score,
+            time_of_day: data.time_of_day,
+            time_of_day_string: data.time_of_day_string.clone(),
+            weather_condition: data.weather_condition,
+            robots,
+            selected_robot: selected,
+            comparison: self.split_screen,
+            event_log: &data.event_log,
+            manual_control_available: data.manual_control_available,
+            manual_mode: data.manual_mode,
+            debug: &data.debug,
+            ticks: data.ticks,
+            history: &data.history,
+            custom_values: &data.custom_values,
+            backpack_changes: &data.backpack_changes,
+            terminated: data.terminated,
+            content_icons: data.robot_backpack_contents.keys()
+                .filter_map(|content| self.renderer.content_icon(content).map(|icon| (content_name(content), icon)))
+                .collect(),
+        }
     }
 
-    pub(super) fn render(&mut self, data: &ChannelData) {
-        if self.ui.is_mouse_grabbed() {
-            self.update_camera(); // This needs to be done first
-        } else {
-            set_camera(self.camera.get_actual_camera());
+    fn move_camera_to_tile(&mut self, data: &ChannelData, (x, z): (usize, usize)) {
+        let elevation = data.explored_world_map
+            .get(x)
+            .and_then(|row| row.get(z))
+            .and_then(|tile| tile.as_ref())
+            .map_or(1, |tile| tile.elevation.max(1));
+
+        self.camera.look_at(vec3(x as f32 + 0.5, elevation as f32, z as f32 + 0.5));
+    }
+
+    pub(super) fn render(&mut self, robots: &[RobotView], plugins: &mut [Box<dyn OlympusPlugin>]) {
+        let selected = self.ui.selected_robot().min(robots.len() - 1);
+        let data = robots[selected].data;
+
+        if selected != self.focused_robot {
+            self.focused_robot = selected;
+            self.move_camera_to_tile(data, data.robot_coordinates);
+        }
+        if let Some(target) = self.ui.take_camera_target() {
+            self.move_camera_to_tile(data, target);
         }
+        if self.camera_mode == CameraMode::FollowRobot && self.followed_coordinates != Some(data.robot_coordinates) {
+            self.followed_coordinates = Some(data.robot_coordinates);
+            self.move_camera_to_tile(data, data.robot_coordinates);
+        }
+
+        self.camera.update(); // This needs to be done first
+        self.weather_effects.update(data.weather_condition, self.camera.get_position());
         
-        self.render_game(data, self.camera.get_front());
+        if self.split_screen && robots.len() >= 2 {
+            let half_width = screen_width() / 2.0;
+            self.render_game(robots, plugins, 0, Some(Rect::new(0.0, 0.0, half_width, screen_height())));
+            self.render_game(robots, plugins, 1, Some(Rect::new(half_width, 0.0, half_width, screen_height())));
+        } else {
+            self.render_game(robots, plugins, selected, None);
+        }
+        // The alerts keep working while the HUD is hidden, only their notifications are not drawn
+        set_default_camera();
+        let props = self.ui_props(robots, selected);
+        self.ui.update(&props);
         if self.show_hud {
-            self.render_ui(data);
+            self.ui.render(props);
+            for plugin in plugins.iter_mut() {
+                plugin.draw_ui(&mut *root_ui());
+            }
         }
     }
 }
\ No newline at end of file
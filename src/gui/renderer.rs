@@ -1,5 +1,11 @@
+use std::path::Path;
+
+use macroquad::miniquad::{BlendFactor, BlendState, BlendValue, Equation};
 use macroquad::prelude::*;
-use robotics_lib::world::{environmental_conditions::DayTime, tile::{Content, Tile, TileType}};
+use robotics_lib::world::{environmental_conditions::{DayTime, WeatherType}, tile::{Content, Tile, TileType}};
+use crate::channel::DebugDrawings;
+use crate::world_info::{content_amount, content_cube_size, content_fill, content_stack_height, FILL_GAUGE_OFFSET, FILL_GAUGE_SIZE};
+use super::lighting::{Lighting, TERRAIN_FRAGMENT_SHADER, TERRAIN_VERTEX_SHADER};
 
 struct Textures {
     robot: Texture2D,
@@ -59,37 +65,81 @@ impl Textures {
         self.jolly_block_content.set_filter(FilterMode::Nearest);
         self.scarecrow_content.set_filter(FilterMode::Nearest);
     }
+
+    /// `None` for `Content::None`, that is drawn with the texture of the tile
+    fn content(&self, content: &Content) -> Option<&Texture2D> {
+        match content {
+            Content::Rock(_) => Some(&self.rock_content),
+            Content::Tree(_) => Some(&self.tree_content),
+            Content::Garbage(_) => Some(&self.garbage_content),
+            Content::Fire => Some(&self.fire_content),
+            Content::Coin(_) => Some(&self.coin_content),
+            Content::Bin(_) => Some(&self.bin_content),
+            Content::Crate(_) => Some(&self.crate_content),
+            Content::Bank(_) => Some(&self.bank_content),
+            Content::Water(_) => Some(&self.water_content),
+            Content::Market(_) => Some(&self.market_content),
+            Content::Fish(_) => Some(&self.fish_content),
+            Content::Building => Some(&self.building_content),
+            Content::Bush(_) => Some(&self.bush_content),
+            Content::JollyBlock(_) => Some(&self.jolly_block_content),
+            Content::Scarecrow => Some(&self.scarecrow_content),
+            Content::None => None,
+        }
+    }
+}
+
+/// Loads `file` from the texture pack folder if it's there and it's a valid PNG, otherwise uses the built-in texture
+fn load_texture(texture_pack: Option<&Path>, file: &str, built_in: &'static [u8]) -> Texture2D {
+    let image = texture_pack
+        .and_then(|folder| std::fs::read(folder.join(file)).ok())
+        .and_then(|bytes| image::load_from_memory(&bytes).ok())
+        .map(|image| image.to_rgba8())
+        .filter(|image| image.width() <= u16::MAX as u32 && image.height() <= u16::MAX as u32);
+
+    match image {
+        Some(image) => Texture2D::from_rgba8(image.width() as u16, image.height() as u16, image.as_raw()),
+        None => Texture2D::from_file_with_format(built_in, Some(ImageFormat::Png)),
+    }
+}
+
+macro_rules! texture {
+    ($texture_pack:expr, $file:literal) => {
+        load_texture($texture_pack, $file, include_bytes!(concat!("../../assets/", $file)))
+    };
 }
 
-impl Default for Textures {
-    fn default() -> Self {
+impl Textures {
+    /// A texture pack is a folder with the same layout of the assets folder (e.g. `tiles/sand.png`),
+    /// missing textures are replaced by the built-in ones
+    fn load(texture_pack: Option<&Path>) -> Self {
         Self {
-            robot: Texture2D::from_file_with_format(include_bytes!("../../assets/robot/creeper.png"), Some(ImageFormat::Png)),
-            water_block: Texture2D::from_file_with_format(include_bytes!("../../assets/tiles/underwater_opaque.png"), Some(ImageFormat::Png)),
-            sand_block: Texture2D::from_file_with_format(include_bytes!("../../assets/tiles/sand.png"), Some(ImageFormat::Png)),
-            grass_block: Texture2D::from_file_with_format(include_bytes!("../../assets/tiles/green_concrete_powder.png"),Some(ImageFormat::Png)),
-            street_block: Texture2D::from_file_with_format(include_bytes!("../../assets/tiles/dirt_path_top.png"), Some(ImageFormat::Png)),
-            hill_block: Texture2D::from_file_with_format(include_bytes!("../../assets/tiles/dirt.png"), Some(ImageFormat::Png)),
-            mountain_block: Texture2D::from_file_with_format(include_bytes!("../../assets/tiles/stone.png"), Some(ImageFormat::Png)),
-            snow_block: Texture2D::from_file_with_format(include_bytes!("../../assets/tiles/snow.png"), Some(ImageFormat::Png)),
-            lava_block: Texture2D::from_file_with_format(include_bytes!("../../assets/tiles/lava.png"), Some(ImageFormat::Png)),
-            teleport_block: Texture2D::from_file_with_format(include_bytes!("../../assets/tiles/beacon.png"), Some(ImageFormat::Png)),
-            wall_block: Texture2D::from_file_with_format(include_bytes!("../../assets/tiles/stone_bricks.png"), Some(ImageFormat::Png)),
-            rock_content: Texture2D::from_file_with_format(include_bytes!("../../assets/contents/cobblestone.png"), Some(ImageFormat::Png)),
-            tree_content: Texture2D::from_file_with_format(include_bytes!("../../assets/contents/oak_log.png"), Some(ImageFormat::Png)),
-            garbage_content: Texture2D::from_file_with_format(include_bytes!("../../assets/contents/coal_block.png"), Some(ImageFormat::Png)),
-            fire_content: Texture2D::from_file_with_format(include_bytes!("../../assets/contents/fire.png"), Some(ImageFormat::Png)),
-            coin_content: Texture2D::from_file_with_format(include_bytes!("../../assets/contents/gold_ore.png"), Some(ImageFormat::Png)),
-            bin_content: Texture2D::from_file_with_format(include_bytes!("../../assets/contents/dropper_front_vertical.png"), Some(ImageFormat::Png)),
-            crate_content: Texture2D::from_file_with_format(include_bytes!("../../assets/contents/barrel.png"), Some(ImageFormat::Png)),
-            bank_content: Texture2D::from_file_with_format(include_bytes!("../../assets/contents/gold_block.png"), Some(ImageFormat::Png)),
-            water_content: Texture2D::from_file_with_format(include_bytes!("../../assets/contents/water.png"), Some(ImageFormat::Png)),
-            market_content: Texture2D::from_file_with_format(include_bytes!("../../assets/contents/emerald_block.png"), Some(ImageFormat::Png)),
-            fish_content: Texture2D::from_file_with_format(include_bytes!("../../assets/contents/orange_glazed_terracotta.png"), Some(ImageFormat::Png)),
-            building_content: Texture2D::from_file_with_format(include_bytes!("../../assets/contents/bricks.png"), Some(ImageFormat::Png)),
-            bush_content: Texture2D::from_file_with_format(include_bytes!("../../assets/contents/azalea_top.png"), Some(ImageFormat::Png)),
-            jolly_block_content: Texture2D::from_file_with_format(include_bytes!("../../assets/contents/jack_o_lantern.png"), Some(ImageFormat::Png)),
-            scarecrow_content: Texture2D::from_file_with_format(include_bytes!("../../assets/contents/hay_block_side.png"), Some(ImageFormat::Png)),
+            robot: texture!(texture_pack, "robot/creeper.png"),
+            water_block: texture!(texture_pack, "tiles/underwater_opaque.png"),
+            sand_block: texture!(texture_pack, "tiles/sand.png"),
+            grass_block: texture!(texture_pack, "tiles/green_concrete_powder.png"),
+            street_block: texture!(texture_pack, "tiles/dirt_path_top.png"),
+            hill_block: texture!(texture_pack, "tiles/dirt.png"),
+            mountain_block: texture!(texture_pack, "tiles/stone.png"),
+            snow_block: texture!(texture_pack, "tiles/snow.png"),
+            lava_block: texture!(texture_pack, "tiles/lava.png"),
+            teleport_block: texture!(texture_pack, "tiles/beacon.png"),
+            wall_block: texture!(texture_pack, "tiles/stone_bricks.png"),
+            rock_content: texture!(texture_pack, "contents/cobblestone.png"),
+            tree_content: texture!(texture_pack, "contents/oak_log.png"),
+            garbage_content: texture!(texture_pack, "contents/coal_block.png"),
+            fire_content: texture!(texture_pack, "contents/fire.png"),
+            coin_content: texture!(texture_pack, "contents/gold_ore.png"),
+            bin_content: texture!(texture_pack, "contents/dropper_front_vertical.png"),
+            crate_content: texture!(texture_pack, "contents/barrel.png"),
+            bank_content: texture!(texture_pack, "contents/gold_block.png"),
+            water_content: texture!(texture_pack, "contents/water.png"),
+            market_content: texture!(texture_pack, "contents/emerald_block.png"),
+            fish_content: texture!(texture_pack, "contents/orange_glazed_terracotta.png"),
+            building_content: texture!(texture_pack, "contents/bricks.png"),
+            bush_content: texture!(texture_pack, "contents/azalea_top.png"),
+            jolly_block_content: texture!(texture_pack, "contents/jack_o_lantern.png"),
+            scarecrow_content: texture!(texture_pack, "contents/hay_block_side.png"),
         }
     }
 }
@@ -197,22 +247,61 @@ void main() {
 }
 ";
 
+const FOG_COLOR: Color = Color::new(0.75, 0.75, 0.78, 1.0);
+const DEBUG_PATH_COLOR: Color = Color::new(0.2, 0.8, 1.0, 1.0);
+const DEBUG_HIGHLIGHT_COLOR: Color = Color::new(1.0, 0.2, 0.8, 1.0);
+const DEBUG_MARKER_HEIGHT: f32 = 2.0;
+
 #[derive(Clone)]
 pub(super) struct RendererProps<'a> {
     pub explored_world_map: &'a Vec<Vec<Option<Tile>>>,
+    pub ground_truth: &'a Vec<Vec<Tile>>,
     pub robot_coordinates: (usize, usize),
-    pub time_of_day: DayTime
+    pub robot_color: Color,
+    pub other_robots: Vec<((usize, usize), Color)>,
+    pub time_of_day: DayTime,
+    pub time_of_day_string: &'a str,
+    pub weather_condition: WeatherType,
+    pub highlighted_tiles: Vec<(usize, usize)>,
+    pub debug: &'a DebugDrawings,
+}
+
+/// Which map is drawn: what the robot knows or the world as it was generated
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum WorldView {
+    Explored,
+    GroundTruth,
+    GroundTruthDimmed,
+}
+
+impl WorldView {
+    pub(super) fn next(self) -> Self {
+        match self {
+            WorldView::Explored => WorldView::GroundTruth,
+            WorldView::GroundTruth => WorldView::GroundTruthDimmed,
+            WorldView::GroundTruthDimmed => WorldView::Explored,
+        }
+    }
+}
+
+pub(super) struct RendererSettings {
+    pub daylight_cycle: bool,
+    pub fog_of_war: bool,
+    pub world_view: WorldView,
+    pub show_grid: bool,
+    pub clear_background: bool,
 }
 
 pub(super) struct Renderer {
-    world_map_size: usize,
     textures: Textures,
-    material: Material
+    material: Material,
+    terrain_material: Material,
+    emissive_material: Material
 }
 
 impl Renderer {
-    pub(super) fn new(world_map_size: usize) -> Self {
-        let textures = Textures::default();
+    pub(super) fn new(texture_pack: Option<&Path>) -> Self {
+        let textures = Textures::load(texture_pack);
         textures.init();
 
         let uniforms: Vec<(String, UniformType)> = vec![
@@ -230,18 +319,118 @@ impl Renderer {
                 ..Default::default() //custom pipeline parameters
             },
         ).expect("Error creating material for sky shader");
+
+        let terrain_material = Self::load_terrain_material();
+        terrain_material.set_uniform("u_emissive", 0.0_f32);
+        let emissive_material = Self::load_terrain_material();
+        emissive_material.set_uniform("u_emissive", 0.8_f32);
         
         Self {
-            world_map_size,
             textures,
-            material
+            material,
+            terrain_material,
+            emissive_material
         }
     }
 
-    fn draw_background(&self, props: &RendererProps, daylight_cycle: bool, camera_front: Vec3) {
-        clear_background(SKYBLUE);
+    /// Texture of a content, used as its icon in the HUD
+    pub(super) fn content_icon(&self, content: &Content) -> Option<Texture2D> {
+        self.textures.content(content).cloned()
+    }
 
+    fn load_terrain_material() -> Material {
+        let uniforms: Vec<(String, UniformType)> = vec![
+            ("u_light_color".to_string(), UniformType::Float3),
+            ("u_ambient".to_string(), UniformType::Float3),
+            ("u_emissive".to_string(), UniformType::Float1),
+            ("u_time".to_string(), UniformType::Float1),
+        ];
+        load_material(
+            ShaderSource::Glsl {
+                vertex: TERRAIN_VERTEX_SHADER,
+                fragment: TERRAIN_FRAGMENT_SHADER,
+            },
+            MaterialParams {
+                uniforms,
+                pipeline_params: PipelineParams {
+                    depth_write: true,
+                    depth_test: Comparison::LessOrEqual,
+                    color_blend: Some(BlendState::new(
+                        Equation::Add,
+                        BlendFactor::Value(BlendValue::SourceAlpha),
+                        BlendFactor::OneMinusValue(BlendValue::SourceAlpha),
+                    )),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ).expect("Error creating material for terrain shader")
+    }
+
+    fn lighting(props: &RendererProps, daylight_cycle: bool) -> Lighting {
         if daylight_cycle {
+            Lighting::new(props.time_of_day, props.time_of_day_string)
+        } else {
+            Lighting::default()
+        }
+    }
+
+    fn set_lighting_uniforms(&self, lighting: &Lighting) {
+        for material in [&self.terrain_material, &self.emissive_material] {
+            material.set_uniform("u_light_color", lighting.color);
+            material.set_uniform("u_ambient", lighting.ambient);
+            material.set_uniform("u_time", get_time() as f32);
+        }
+    }
+
+    fn is_emissive_tile(tile: &Tile) -> bool {
+        matches!(tile.tile_type, TileType::Lava)
+    }
+
+    fn is_emissive_content(content: &Content) -> bool {
+        matches!(content, Content::Fire | Content::JollyBlock(_))
+    }
+
+    fn sky_brightness(weather_condition: WeatherType) -> f32 {
+        match weather_condition {
+            WeatherType::Sunny => 1.0,
+            WeatherType::Rainy => 0.7,
+            WeatherType::Foggy => 0.9,
+            WeatherType::TropicalMonsoon => 0.4,
+            WeatherType::TrentinoSnow => 0.85,
+        }
+    }
+
+    /// Returns how much a point is hidden by the fog, from 0.0 (clear) to 1.0 (fully hidden)
+    fn fog_factor(weather_condition: WeatherType, camera_position: Vec3, point: Vec3) -> f32 {
+        let (fog_start, fog_end) = match weather_condition {
+            WeatherType::Foggy => (8.0, 40.0),
+            WeatherType::TropicalMonsoon => (30.0, 120.0),
+            _ => return 0.0,
+        };
+
+        ((camera_position.distance(point) - fog_start) / (fog_end - fog_start)).clamp(0.0, 1.0)
+    }
+
+    /// Rows and columns of the world, the generated map is complete while the explored one may still be a placeholder
+    fn world_dimensions(props: &RendererProps) -> (usize, usize) {
+        if props.ground_truth.is_empty() {
+            (props.explored_world_map.len(), props.explored_world_map.iter().map(|row| row.len()).max().unwrap_or(0))
+        } else {
+            (props.ground_truth.len(), props.ground_truth.iter().map(|row| row.len()).max().unwrap_or(0))
+        }
+    }
+
+    fn draw_background(&self, props: &RendererProps, settings: &RendererSettings, camera_front: Vec3) {
+        let brightness = Self::sky_brightness(props.weather_condition);
+        if settings.clear_background {
+            match props.weather_condition {
+                WeatherType::Foggy => clear_background(FOG_COLOR),
+                _ => clear_background(Color::new(SKYBLUE.r * brightness, SKYBLUE.g * brightness, SKYBLUE.b * brightness, 1.0)),
+            }
+        }
+
+        if settings.daylight_cycle {
             self.material.set_uniform("u_time", get_time() as f32);
             self.material.set_uniform("u_camera_target", camera_front);
             let hue = match props.time_of_day {
@@ -249,12 +438,17 @@ impl Renderer {
                 DayTime::Afternoon => vec3(230., 80., 11.),
                 DayTime::Night => vec3(4., 26., 64.),
             } / 255.0; //normalize color values
+            let hue = match props.weather_condition {
+                WeatherType::Foggy => hue.lerp(vec3(FOG_COLOR.r, FOG_COLOR.g, FOG_COLOR.b), 0.7),
+                _ => hue * brightness,
+            };
             self.material.set_uniform("u_hue", hue);
 
+            let (rows, columns) = Self::world_dimensions(props);
             gl_use_material(&self.material);
             draw_sphere(
-                vec3(self.world_map_size as f32 / 2.0, 0.0, self.world_map_size as f32 / 2.0), 
-                self.world_map_size as f32 * 2.0, 
+                vec3(rows as f32 / 2.0, 0.0, columns as f32 / 2.0), 
+                rows.max(columns) as f32 * 2.0, 
                 None,
                 WHITE,
             );
@@ -262,33 +456,45 @@ impl Renderer {
         }
     }
 
-    fn draw_grid(&self, spacing: f32, axes_color: Color, other_color: Color) {
-        let slices = self.world_map_size as u32;
-        
-        for i in 0..slices + 1 {
+    fn draw_grid(&self, (rows, columns): (usize, usize), spacing: f32, axes_color: Color, other_color: Color) {
+        let (width, depth) = (rows as f32 * spacing, columns as f32 * spacing);
+
+        //lines along the columns
+        for i in 0..rows + 1 {
             let color = if i == 0 { axes_color } else { other_color };
-            
-            //horizontal lines
             draw_line_3d(
                 vec3(i as f32 * spacing, 0.0, 0.0),
-                vec3(i as f32 * spacing, 0., slices as f32 * spacing),
+                vec3(i as f32 * spacing, 0., depth),
                 color,
             );
-            //vertical lines
+        }
+        //lines along the rows
+        for i in 0..columns + 1 {
+            let color = if i == 0 { axes_color } else { other_color };
             draw_line_3d(
                 vec3(0.0, 0.0, i as f32 * spacing),
-                vec3(slices as f32 * spacing, 0., i as f32 * spacing),
+                vec3(width, 0., i as f32 * spacing),
                 color,
             );
         }
     }
 
-    fn render_explored_map(&self, props: &RendererProps) {    
+    fn render_explored_map(&self, props: &RendererProps, world_view: WorldView, lighting: &Lighting, camera_position: Vec3) {    
         let offset = 0.5;
 
         for (x, row) in props.explored_world_map.iter().enumerate() {
             for (z, tile) in row.iter().enumerate() {
-                if let Some(tile) = tile {
+                // Tiles the robot hasn't explored yet come from the generated world, if requested
+                let shown_tile = match (tile, world_view) {
+                    (Some(tile), _) => Some((tile, false)),
+                    (None, WorldView::Explored) => None,
+                    (None, _) => props.ground_truth
+                        .get(x)
+                        .and_then(|row| row.get(z))
+                        .map(|tile| (tile, world_view == WorldView::GroundTruthDimmed)),
+                };
+
+                if let Some((tile, dimmed)) = shown_tile {
                     let mut tile_color = WHITE;
                     let tile_texture = match tile.tile_type {
                         TileType::DeepWater => { tile_color = GRAY; &self.textures.water_block }
@@ -300,36 +506,35 @@ impl Renderer {
                         TileType::Mountain => &self.textures.mountain_block,
                         TileType::Snow => &self.textures.snow_block,
                         TileType::Lava => &self.textures.lava_block,
-                        TileType::Teleport(_) => &self.textures.teleport_block,
+                        TileType::Teleport(activated) => { if !activated { tile_color = DARKGRAY; } &self.textures.teleport_block }
                         TileType::Wall => &self.textures.wall_block,
                     };
 
                     let mut content_color = WHITE;
                     let content_texture = match tile.content {
-                        Content::Rock(_) => &self.textures.rock_content,
-                        Content::Tree(_) => &self.textures.tree_content,
-                        Content::Garbage(_) => &self.textures.garbage_content,
-                        Content::Fire => &self.textures.fire_content,
-                        Content::Coin(_) => &self.textures.coin_content,
-                        Content::Bin(_) => &self.textures.bin_content,
-                        Content::Crate(_) => &self.textures.crate_content,
-                        Content::Bank(_) => &self.textures.bank_content,
-                        Content::Water(_) => &self.textures.water_content,
-                        Content::Market(_) => &self.textures.market_content,
-                        Content::Fish(_) => &self.textures.fish_content,
-                        Content::Building => &self.textures.building_content,
                         Content::Bush(_) => { content_color = LIGHTGRAY; &self.textures.bush_content }
-                        Content::JollyBlock(_) => &self.textures.jolly_block_content,
-                        Content::Scarecrow => &self.textures.scarecrow_content,
-                        Content::None => tile_texture,
+                        _ => self.textures.content(&tile.content).unwrap_or(tile_texture),
                     };
                     
                     let elevation = if tile.elevation == 0 { tile.elevation + 1 } else { tile.elevation };
-                    draw_affine_parallelepiped(
-                        vec3(x as f32, 0.0, z as f32), //x as f32 * Vec3::X + z as f32 * Vec3::Z,
-                        1.0 * Vec3::X,
-                        (elevation as f32) * Vec3::Y,
-                        1.0 * Vec3::Z,
+                    let fog = Self::fog_factor(props.weather_condition, camera_position, vec3(offset + x as f32, elevation as f32, offset + z as f32));
+                    if fog >= 1.0 {
+                        continue;
+                    }
+                    tile_color.a = 1.0 - fog;
+                    content_color.a = 1.0 - fog;
+                    if dimmed {
+                        for color in [&mut tile_color, &mut content_color] {
+                            color.r *= 0.35;
+                            color.g *= 0.35;
+                            color.b *= 0.35;
+                        }
+                    }
+
+                    gl_use_material(if Self::is_emissive_tile(tile) { &self.emissive_material } else { &self.terrain_material });
+                    lighting.draw_block(
+                        vec3(x as f32, 0.0, z as f32),
+                        vec3(1.0, elevation as f32, 1.0),
                         Some(tile_texture),
                         tile_color
                     );
@@ -344,44 +549,294 @@ impl Renderer {
                             );
                         }
                         _ => {
-                            draw_cube(
-                                vec3(offset + x as f32, 0.25 + elevation as f32, offset + z as f32),
-                                vec3(0.5, 0.5, 0.5),
-                                Some(content_texture),
-                                content_color
-                            );
+                            gl_use_material(if Self::is_emissive_content(&tile.content) { &self.emissive_material } else { &self.terrain_material });
+                            let stack_height = content_stack_height(&tile.content);
+                            let cube_size = content_cube_size(stack_height);
+                            for level in 0..stack_height {
+                                lighting.draw_cube(
+                                    vec3(offset + x as f32, cube_size / 2.0 + level as f32 * cube_size + elevation as f32, offset + z as f32),
+                                    vec3(cube_size, cube_size, cube_size),
+                                    Some(content_texture),
+                                    content_color
+                                );
+                            }
+
+                            if let Some(fill) = content_fill(&tile.content) {
+                                gl_use_default_material();
+                                Self::draw_fill_gauge(vec3(offset + FILL_GAUGE_OFFSET + x as f32, elevation as f32, offset + z as f32), fill, content_color.a);
+                            }
                         }
                     }
                 }
             }
         }
+        gl_use_default_material();
     }
 
-    fn render_robot(&self, props: &RendererProps) {
+    fn draw_fill_gauge(base: Vec3, fill: f32, alpha: f32) {
+        let (width, height) = FILL_GAUGE_SIZE;
+
+        draw_cube(
+            base + vec3(0.0, height * fill / 2.0, 0.0),
+            vec3(width, height * fill, width),
+            None,
+            Color::new(0.1, 0.8, 0.2, alpha)
+        );
+        draw_cube_wires(
+            base + vec3(0.0, height / 2.0, 0.0),
+            vec3(width, height, width),
+            Color::new(0.0, 0.0, 0.0, alpha)
+        );
+    }
+
+    /// Screen position of a point of the world, `None` if it's behind the camera
+    fn project(view_projection: Mat4, viewport: Rect, position: Vec3) -> Option<Vec2> {
+        let clip = view_projection * position.extend(1.0);
+        if clip.w <= 0.0 {
+            return None;
+        }
+        let ndc = clip.truncate() / clip.w;
+
+        Some(vec2(viewport.x + (ndc.x + 1.0) / 2.0 * viewport.w, viewport.y + (1.0 - ndc.y) / 2.0 * viewport.h))
+    }
+
+    /// Center of the top face of a tile, unexplored tiles are considered at elevation 1
+    fn tile_top(props: &RendererProps, (x, z): (usize, usize)) -> Vec3 {
+        let elevation = props.explored_world_map
+            .get(x)
+            .and_then(|row| row.get(z))
+            .and_then(|tile| tile.as_ref())
+            .map_or(1, |tile| tile.elevation.max(1));
+
+        vec3(x as f32 + 0.5, elevation as f32, z as f32 + 0.5)
+    }
+
+    fn render_debug_drawings(&self, props: &RendererProps) {
+        for path in props.debug.paths.iter() {
+            for step in path.windows(2) {
+                draw_line_3d(
+                    Self::tile_top(props, step[0]) + vec3(0.0, 0.1, 0.0),
+                    Self::tile_top(props, step[1]) + vec3(0.0, 0.1, 0.0),
+                    DEBUG_PATH_COLOR
+                );
+            }
+            if let Some(last) = path.last() {
+                draw_cube(Self::tile_top(props, *last) + vec3(0.0, 0.1, 0.0), vec3(0.2, 0.2, 0.2), None, DEBUG_PATH_COLOR);
+            }
+        }
+
+        for coordinates in props.debug.highlighted_tiles.iter() {
+            draw_cube_wires(Self::tile_top(props, *coordinates) + vec3(0.0, 0.05, 0.0), vec3(1.0, 0.1, 1.0), DEBUG_HIGHLIGHT_COLOR);
+        }
+
+        for marker in props.debug.markers.iter() {
+            let top = Self::tile_top(props, marker.coordinates);
+            draw_line_3d(top, top + vec3(0.0, DEBUG_MARKER_HEIGHT, 0.0), marker.color);
+            draw_cube(top + vec3(0.0, DEBUG_MARKER_HEIGHT, 0.0), vec3(0.3, 0.3, 0.3), None, marker.color);
+        }
+    }
+
+    /// Draws the labels of the debug markers, call it after switching to the screen camera
+    pub(super) fn render_debug_labels(&self, props: &RendererProps, camera: &Camera3D, viewport: Rect) {
+        let view_projection = camera.matrix();
+
+        for marker in props.debug.markers.iter().filter(|marker| !marker.label.is_empty()) {
+            let position = Self::tile_top(props, marker.coordinates) + vec3(0.0, DEBUG_MARKER_HEIGHT + 0.4, 0.0);
+            if let Some(screen) = Self::project(view_projection, viewport, position) {
+                let dimensions = measure_text(&marker.label, None, 20, 1.0);
+                draw_text(&marker.label, screen.x - dimensions.width / 2.0, screen.y, 20.0, marker.color);
+            }
+        }
+    }
+
+    pub(super) fn render_content_labels(&self, props: &RendererProps, camera: &Camera3D, viewport: Rect, max_distance: f32) {
+        let offset = 0.5;
+        let view_projection = camera.matrix();
+        let range = |center: f32, len: usize| {
+            let start = (center - max_distance).max(0.0) as usize;
+            let end = ((center + max_distance).max(0.0) as usize).min(len);
+            start..end
+        };
+
+        for x in range(camera.position.x, props.explored_world_map.len()) {
+            let row = &props.explored_world_map[x];
+            for z in range(camera.position.z, row.len()) {
+                let Some(tile) = &row[z] else {
+                    continue;
+                };
+                let label = match (content_amount(&tile.content), &tile.content) {
+                    (_, Content::Bin(range) | Content::Crate(range) | Content::Bank(range)) => format!("{}/{}", range.start, range.end),
+                    (Some(amount), _) => amount.to_string(),
+                    (None, _) => continue,
+                };
+
+                let elevation = if tile.elevation == 0 { tile.elevation + 1 } else { tile.elevation };
+                let stack_height = content_stack_height(&tile.content);
+                let top = stack_height as f32 * content_cube_size(stack_height) + elevation as f32;
+                let position = vec3(offset + x as f32, top + 0.3, offset + z as f32);
+                if camera.position.distance(position) > max_distance {
+                    continue;
+                }
+
+                let Some(screen) = Self::project(view_projection, viewport, position) else {
+                    continue; // Behind the camera
+                };
+                let dimensions = measure_text(&label, None, 20, 1.0);
+                draw_text(&label, screen.x - dimensions.width / 2.0, screen.y, 20.0, WHITE);
+            }
+        }
+    }
+
+    fn render_highlighted_tiles(&self, props: &RendererProps) {
+        let offset = 0.5;
+        let pulse = 0.5 + 0.5 * (get_time() as f32 * 4.0).sin();
+        let color = Color::new(1.0, 0.9, 0.0, 0.6 + 0.4 * pulse);
+
+        for (x, z) in props.highlighted_tiles.iter() {
+            let elevation = props.explored_world_map
+                .get(*x)
+                .and_then(|row| row.get(*z))
+                .and_then(|tile| tile.as_ref())
+                .map_or(1, |tile| tile.elevation.max(1));
+
+            draw_cube_wires(
+                vec3(offset + *x as f32, elevation as f32 / 2.0, offset + *z as f32),
+                vec3(1.05, elevation as f32 + 0.05, 1.05),
+                color
+            );
+            draw_line_3d(
+                vec3(offset + *x as f32, elevation as f32, offset + *z as f32),
+                vec3(offset + *x as f32, elevation as f32 + 10.0, offset + *z as f32),
+                color
+            );
+        }
+    }
+
+    fn render_teleport_beams(&self, props: &RendererProps) {
         let offset = 0.5;
-        let (x, z) = props.robot_coordinates;
+        let beam_height = 30.0;
+        let time = get_time() as f32;
+
+        for (x, row) in props.explored_world_map.iter().enumerate() {
+            for (z, tile) in row.iter().enumerate() {
+                let Some(tile) = tile else {
+                    continue;
+                };
+                if !matches!(tile.tile_type, TileType::Teleport(true)) {
+                    continue;
+                }
+
+                let elevation = if tile.elevation == 0 { tile.elevation + 1 } else { tile.elevation };
+                let pulse = 0.5 + 0.5 * (time * 2.0 + (x + z) as f32).sin();
+                let width = 0.3 + 0.1 * pulse;
+                draw_cube(
+                    vec3(offset + x as f32, elevation as f32 + beam_height / 2.0, offset + z as f32),
+                    vec3(width, beam_height, width),
+                    None,
+                    Color::new(0.4, 0.9, 1.0, 0.15 + 0.2 * pulse)
+                );
+
+                // A ring of light travelling up the beam
+                let ring_height = (time * 5.0 + (x * 7 + z * 3) as f32) % beam_height;
+                draw_cube_wires(
+                    vec3(offset + x as f32, elevation as f32 + ring_height, offset + z as f32),
+                    vec3(0.8, 0.05, 0.8),
+                    Color::new(0.6, 1.0, 1.0, 1.0 - ring_height / beam_height)
+                );
+            }
+        }
+    }
+
+    fn render_fog_of_war(&self, props: &RendererProps) {
+        let fog_height = 0.01;
+        let max_alpha = 0.75;
+        let frontier_radius: isize = 2;
+
+        for (x, row) in props.explored_world_map.iter().enumerate() {
+            for (z, tile) in row.iter().enumerate() {
+                if tile.is_some() {
+                    continue;
+                }
+
+                // Distance (in tiles) to the closest explored tile, used to fade the fog near the frontier
+                let mut distance = frontier_radius + 1;
+                for dx in -frontier_radius..=frontier_radius {
+                    for dz in -frontier_radius..=frontier_radius {
+                        let (nx, nz) = (x as isize + dx, z as isize + dz);
+                        if nx < 0 || nz < 0 {
+                            continue;
+                        }
+                        let explored = props.explored_world_map
+                            .get(nx as usize)
+                            .and_then(|row| row.get(nz as usize))
+                            .map_or(false, |tile| tile.is_some());
+                        if explored {
+                            distance = distance.min(dx.abs().max(dz.abs()));
+                        }
+                    }
+                }
+
+                let alpha = max_alpha * distance as f32 / (frontier_radius + 1) as f32;
+                draw_affine_parallelepiped(
+                    vec3(x as f32, 0.0, z as f32),
+                    1.0 * Vec3::X,
+                    fog_height * Vec3::Y,
+                    1.0 * Vec3::Z,
+                    None,
+                    Color::new(0.05, 0.05, 0.08, alpha)
+                );
+            }
+        }
+    }
+
+    fn render_robot_marker(&self, props: &RendererProps, lighting: &Lighting, (x, z): (usize, usize), color: Color, line_color: Color) {
+        let offset = 0.5;
+        
+        let (rows, columns) = Self::world_dimensions(props);
         
-        if let Some(tile) = &props.explored_world_map[x][z] {
+        if let Some(tile) = props.explored_world_map.get(x).and_then(|row| row.get(z)).and_then(|tile| tile.as_ref()) {
             let elevation = if tile.elevation == 0 { tile.elevation + 1 } else { tile.elevation };
 
             draw_line_3d(
-                vec3(offset + x as f32, self.world_map_size as f32, offset + z as f32),
+                vec3(offset + x as f32, rows.max(columns) as f32, offset + z as f32),
                 vec3(offset + x as f32, elevation as f32, offset + z as f32),
-                GREEN
+                line_color
             );
-            draw_cube(
+            gl_use_material(&self.terrain_material);
+            lighting.draw_cube(
                 vec3(offset + x as f32, offset + elevation as f32, offset + z as f32),
                 vec3(1.0, 1.0, 1.0),
                 Some(&self.textures.robot),
-                WHITE
+                color
             );
+            gl_use_default_material();
+        }
+    }
+
+    fn render_robot(&self, props: &RendererProps, lighting: &Lighting) {
+        self.render_robot_marker(props, lighting, props.robot_coordinates, props.robot_color, GREEN);
+
+        for (coordinates, color) in props.other_robots.iter() {
+            self.render_robot_marker(props, lighting, *coordinates, *color, *color);
         }
     }
 
-    pub(super) fn render(&self, props: RendererProps, daylight_cycle: bool, camera_front: Vec3) {       
-        self.draw_background(&props, daylight_cycle, camera_front);
-        self.draw_grid(1.0, BLACK, DARKGRAY);
-        self.render_explored_map(&props);
-        self.render_robot(&props);
+    pub(super) fn render(&self, props: RendererProps, settings: RendererSettings, camera_position: Vec3, camera_front: Vec3) {       
+        self.draw_background(&props, &settings, camera_front);
+        if settings.show_grid {
+            self.draw_grid(Self::world_dimensions(&props), 1.0, BLACK, DARKGRAY);
+        }
+
+        let lighting = Self::lighting(&props, settings.daylight_cycle);
+        self.set_lighting_uniforms(&lighting);
+        self.render_explored_map(&props, settings.world_view, &lighting, camera_position);
+        self.render_robot(&props, &lighting);
+        self.render_highlighted_tiles(&props);
+        self.render_debug_drawings(&props);
+        self.render_teleport_beams(&props);
+        if settings.fog_of_war {
+            // Translucent geometry goes last so the explored tiles stay visible through it
+            self.render_fog_of_war(&props);
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,110 @@
+use robotics_lib::world::tile::{Content, Tile};
+
+use crate::world_info::{content_name, contents, tile_type_name, tile_types};
+
+/// What the search panel is looking for
+#[derive(Clone, Copy)]
+pub(super) enum SearchTarget {
+    Content(&'static str),
+    TileType(&'static str),
+}
+
+impl SearchTarget {
+    /// Every searchable target, in the order shown by the search panel
+    pub(super) fn all() -> Vec<SearchTarget> {
+        contents().into_iter()
+            .filter(|content| !matches!(content, Content::None))
+            .map(|content| SearchTarget::Content(content_name(&content)))
+            .chain(tile_types().into_iter().map(|tile_type| SearchTarget::TileType(tile_type_name(&tile_type))))
+            .collect()
+    }
+
+    pub(super) fn name(&self) -> &'static str {
+        match self {
+            SearchTarget::Content(name) | SearchTarget::TileType(name) => name,
+        }
+    }
+
+    pub(super) fn label(&self) -> String {
+        match self {
+            SearchTarget::Content(name) => format!("Content: {}", name),
+            SearchTarget::TileType(name) => format!("Tile: {}", name),
+        }
+    }
+
+    pub(super) fn matches(&self, tile: &Tile) -> bool {
+        match self {
+            SearchTarget::Content(name) => content_name(&tile.content) == *name,
+            SearchTarget::TileType(name) => tile_type_name(&tile.tile_type) == *name,
+        }
+    }
+
+    /// Explored tiles matching the target, sorted by distance from `origin`
+    pub(super) fn search(&self, explored_world_map: &[Vec<Option<Tile>>], origin: (usize, usize)) -> Vec<((usize, usize), usize)> {
+        let mut results = explored_world_map.iter()
+            .enumerate()
+            .flat_map(|(x, row)| row.iter().enumerate().map(move |(z, tile)| (x, z, tile)))
+            .filter(|(_, _, tile)| tile.as_ref().map_or(false, |tile| self.matches(tile)))
+            .map(|(x, z, _)| ((x, z), x.abs_diff(origin.0) + z.abs_diff(origin.1)))
+            .collect::<Vec<_>>();
+        results.sort_by_key(|(_, distance)| *distance);
+
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use robotics_lib::world::tile::{Content, Tile, TileType};
+
+    use super::SearchTarget;
+    use crate::test_fixtures::explored;
+    use crate::world_info::{content_from_name, tile_type_from_name};
+
+    fn map() -> Vec<Vec<Option<Tile>>> {
+        vec![
+            vec![explored(TileType::Grass, Content::Rock(1)), explored(TileType::Grass, Content::None), None, explored(TileType::Sand, Content::Rock(2))],
+            vec![explored(TileType::Grass, Content::None), explored(TileType::Grass, Content::None), None, None],
+            vec![None, explored(TileType::Sand, Content::Rock(1)), explored(TileType::Grass, Content::Tree(1)), None],
+            vec![explored(TileType::Sand, Content::Rock(3)), None, None, explored(TileType::Grass, Content::None)],
+        ]
+    }
+
+    #[test]
+    fn sorts_the_results_by_distance() {
+        let results = SearchTarget::Content("Rock").search(&map(), (1, 1));
+
+        assert_eq!(results, vec![((2, 1), 1), ((0, 0), 2), ((0, 3), 3), ((3, 0), 3)]);
+    }
+
+    #[test]
+    fn searches_tile_types() {
+        let results = SearchTarget::TileType("Sand").search(&map(), (3, 3));
+
+        assert_eq!(results, vec![((0, 3), 3), ((2, 1), 3), ((3, 0), 3)]);
+    }
+
+    #[test]
+    fn ignores_the_unexplored_tiles() {
+        assert!(SearchTarget::Content("Fish").search(&map(), (0, 0)).is_empty());
+        assert!(SearchTarget::TileType("Lava").search(&vec![vec![None; 4]; 4], (0, 0)).is_empty());
+    }
+
+    #[test]
+    fn lists_every_content_and_tile_type_once() {
+        let targets = SearchTarget::all();
+        let contents = targets.iter().filter(|target| matches!(target, SearchTarget::Content(_))).count();
+        let names = targets.iter().map(|target| target.name()).collect::<BTreeSet<_>>();
+        assert_eq!((contents, targets.len(), names.len()), (15, 26, 26));
+
+        for target in targets.iter() {
+            let known = match target {
+                SearchTarget::Content(name) => content_from_name(name, 0, None).is_some(),
+                SearchTarget::TileType(name) => tile_type_from_name(name, None).is_some(),
+            };
+            assert!(known, "{} is not a variant name", target.name());
+        }
+    }
+}
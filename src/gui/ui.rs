@@ -1,22 +1,118 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::rc::Rc;
 use macroquad::prelude::*;
 use macroquad::telemetry::textures_count;
 use macroquad::ui::{root_ui, widgets, Layout};
 use macroquad::hash;
+use robotics_lib::interface::Direction;
 use robotics_lib::world::environmental_conditions::{DayTime, WeatherType};
-use robotics_lib::world::tile::{Content, Tile};
+use robotics_lib::world::tile::{Content, Tile, TileType};
 use sys_info::{cpu_num, cpu_speed, mem_info, os_release, MemInfo};
+use crate::channel::{CustomValue, DebugDrawings, TickMetrics};
+use crate::gui::console::{Console, ConsoleCommand};
 use crate::gui::keyboard_controls::KeyboardControls;
+use crate::gui::alerts::Alerts;
+use crate::gui::layout::{Anchor, HudLayout};
+use crate::gui::RobotView;
+use crate::gui::renderer::WorldView;
+use crate::gui::search::SearchTarget;
+use crate::export::{export_map_obj, export_map_png, export_run_data};
+use crate::manual_control::ManualCommand;
+use crate::world_info::content_name;
+
+struct WeatherIcons {
+    sunny: Texture2D,
+    rainy: Texture2D,
+    foggy: Texture2D,
+    tropical_monsoon: Texture2D,
+    trentino_snow: Texture2D,
+}
+
+impl WeatherIcons {
+    fn get(&self, weather_condition: WeatherType) -> &Texture2D {
+        match weather_condition {
+            WeatherType::Sunny => &self.sunny,
+            WeatherType::Rainy => &self.rainy,
+            WeatherType::Foggy => &self.foggy,
+            WeatherType::TropicalMonsoon => &self.tropical_monsoon,
+            WeatherType::TrentinoSnow => &self.trentino_snow,
+        }
+    }
+}
+
+impl Default for WeatherIcons {
+    fn default() -> Self {
+        Self {
+            sunny: Texture2D::from_file_with_format(include_bytes!("../../assets/weather/sunny.png"), Some(ImageFormat::Png)),
+            rainy: Texture2D::from_file_with_format(include_bytes!("../../assets/weather/rainy.png"), Some(ImageFormat::Png)),
+            foggy: Texture2D::from_file_with_format(include_bytes!("../../assets/weather/foggy.png"), Some(ImageFormat::Png)),
+            tropical_monsoon: Texture2D::from_file_with_format(include_bytes!("../../assets/weather/tropical_moonsoon.png"), Some(ImageFormat::Png)),
+            trentino_snow: Texture2D::from_file_with_format(include_bytes!("../../assets/weather/trentino_snow.png"), Some(ImageFormat::Png)),
+        }
+    }
+}
+
+const MAP_EXPORT_BLOCK_SIZE: usize = 8;
+const BACKPACK_FLASH_DURATION: f64 = 1.0;
+const ENERGY_RATE_TICKS: usize = 20;
+const TELEMETRY_CHART_TICKS: usize = 200;
+const TELEMETRY_CHART_SIZE: Vec2 = vec2(370.0, 120.0);
+const BUILT_IN_SERIES: [&str; 4] = ["Energy", "Score", "Explored tiles", "Backpack items"];
+
+/// Value of a chart series in the metrics of a tick, custom metrics are looked up by name
+fn series_value(metrics: &TickMetrics, name: &str) -> Option<f64> {
+    match name {
+        "Energy" => Some(metrics.robot_energy as f64),
+        "Score" => Some(metrics.robot_score as f64),
+        "Explored tiles" => Some(metrics.explored_tiles as f64),
+        "Backpack items" => Some(metrics.backpack_items as f64),
+        key => metrics.custom.get(key).copied(),
+    }
+}
+
+struct EnergyRates {
+    consumed: f32,
+    recharged: f32,
+    /// Energy lost per tick, negative when the robot is recharging faster than it consumes
+    drain: f32,
+}
 
 pub(crate) struct UI {
-    viewport_width: f32,
-    viewport_height: f32,
+    layout: HudLayout,
+    alerts: Alerts,
     keyboard_controls: KeyboardControls,
+    weather_icons: WeatherIcons,
     show_tile_info: bool,
     show_help: bool,
     show_stats: bool,
+    show_teleports: bool,
+    camera_target: Option<(usize, usize)>,
+    selected_robot: usize,
+    robots_count: usize,
+    manual_commands: Vec<ManualCommand>,
+    robot_facing: Direction,
+    /// Content put by the manual control, one of the contents in the backpack of the focused robot
+    put_content: Option<Content>,
+    next_put_content_requested: bool,
+    show_event_log: bool,
+    show_debug: bool,
+    show_telemetry: bool,
+    telemetry_series: usize,
+    backpack_flash_tick: usize,
+    backpack_flash_start: f64,
+    console: Console,
+    paused: bool,
+    pending_steps: usize,
+    show_grid: bool,
+    show_search: bool,
+    export_requested: bool,
+    export_data_requested: bool,
+    export_model_requested: bool,
+    search_target: usize,
+    search_results: Vec<((usize, usize), usize)>,
+    /// Target, robot and tick of the search results, the map changes only with a new tick
+    search_key: Option<(usize, usize, usize)>,
     quit_requested: bool,
     exit: bool,
     is_mouse_grabbed: bool,
@@ -24,37 +120,84 @@ pub(crate) struct UI {
     old_grab_status: bool,
     tick_time: Rc<RefCell<f32>>,
     daylight_cycle: bool,
+    fog_of_war: bool,
+    content_labels: bool,
+    world_view: WorldView,
 }
 
 pub(super) struct UIProps<'a> {
     pub explored_world_map: &'a Vec<Vec<Option<Tile>>>,
     pub discoverable_tiles: usize,
     pub robot_coordinates: (usize, usize),
+    pub robot_path: &'a VecDeque<(usize, usize)>,
     pub robot_energy: usize,
+    pub robot_max_energy: usize,
     pub robot_backpack_contents: &'a HashMap<Content, usize>,
     pub robot_backpack_size: usize,
     pub robot_score: f32,
     pub time_of_day: DayTime,
     pub time_of_day_string: String,
     pub weather_condition: WeatherType,
+    pub robots: &'a [RobotView<'a>],
+    pub selected_robot: usize,
+    pub comparison: bool,
+    pub event_log: &'a VecDeque<String>,
+    pub manual_control_available: bool,
+    pub manual_mode: bool,
+    pub debug: &'a DebugDrawings,
+    pub ticks: usize,
+    pub history: &'a VecDeque<TickMetrics>,
+    pub custom_values: &'a BTreeMap<String, CustomValue>,
+    pub backpack_changes: &'a BTreeMap<String, isize>,
+    pub content_icons: HashMap<&'static str, Texture2D>,
+    pub terminated: bool,
 }
 
 impl UI {
-    pub(super) fn new(tick_time: Rc<RefCell<f32>>) -> Self {
+    pub(super) fn new(tick_time: Rc<RefCell<f32>>, keyboard_controls: KeyboardControls, daylight_cycle: bool, paused: bool, layout: HudLayout, alerts: Alerts) -> Self {
         Self {
-            viewport_width: screen_width(),
-            viewport_height: screen_height(),
-            keyboard_controls: Default::default(),
+            layout,
+            alerts,
+            keyboard_controls,
+            weather_icons: Default::default(),
             show_tile_info: false,
             show_help: false,
             show_stats: false,
+            show_teleports: false,
+            camera_target: None,
+            selected_robot: 0,
+            robots_count: 1,
+            manual_commands: Vec::new(),
+            robot_facing: Direction::Up,
+            put_content: None,
+            next_put_content_requested: false,
+            show_event_log: false,
+            show_debug: false,
+            show_telemetry: false,
+            telemetry_series: 0,
+            backpack_flash_tick: 0,
+            backpack_flash_start: 0.0,
+            console: Default::default(),
+            paused,
+            pending_steps: 0,
+            show_grid: true,
+            show_search: false,
+            export_requested: false,
+            export_data_requested: false,
+            export_model_requested: false,
+            search_target: 0,
+            search_results: Vec::new(),
+            search_key: None,
             quit_requested: false,
             exit: false,
             is_mouse_grabbed: false,
             mouse_grabbed_flag: true,
             old_grab_status: false,
             tick_time,
-            daylight_cycle: true
+            daylight_cycle,
+            fog_of_war: false,
+            content_labels: false,
+            world_view: WorldView::Explored,
         }
     }
 
@@ -78,6 +221,16 @@ impl UI {
     }
 
     pub(super) fn handle_input(&mut self) {
+        if is_key_pressed(self.keyboard_controls.toggle_console) {
+            self.console.toggle();
+        }
+        if self.console.is_open() {
+            if is_key_pressed(self.keyboard_controls.exit) {
+                self.console.toggle();
+            }
+            return;
+        }
+
         if is_key_pressed(self.keyboard_controls.toggle_tile_info) {
             self.show_tile_info = !self.show_tile_info;
         }
@@ -90,6 +243,37 @@ impl UI {
         if is_key_pressed(self.keyboard_controls.toggle_statistics) {
             self.show_stats = !self.show_stats;
         }
+        if is_key_pressed(self.keyboard_controls.toggle_teleports) {
+            self.show_teleports = !self.show_teleports;
+        }
+        if is_key_pressed(self.keyboard_controls.cycle_world_view) {
+            self.world_view = self.world_view.next();
+        }
+        if is_key_pressed(self.keyboard_controls.export_map) {
+            self.export_requested = true;
+        }
+        if is_key_pressed(self.keyboard_controls.export_data) {
+            self.export_data_requested = true;
+        }
+        if is_key_pressed(self.keyboard_controls.export_model) {
+            self.export_model_requested = true;
+        }
+        if is_key_pressed(self.keyboard_controls.toggle_search) {
+            self.show_search = !self.show_search;
+        }
+        if is_key_pressed(self.keyboard_controls.toggle_event_log) {
+            self.show_event_log = !self.show_event_log;
+        }
+        if is_key_pressed(self.keyboard_controls.toggle_debug) {
+            self.show_debug = !self.show_debug;
+        }
+        if is_key_pressed(self.keyboard_controls.toggle_telemetry) {
+            self.show_telemetry = !self.show_telemetry;
+        }
+        self.handle_manual_control_input();
+        if is_key_pressed(self.keyboard_controls.next_robot) {
+            self.selected_robot = (self.selected_robot + 1) % self.robots_count.max(1);
+        }
         // if is_key_pressed(self.keyboard_controls.take_screenshot) {
         //     //set_default_camera();
         //     get_screen_data().export_png("screenshots/screenshot.png");
@@ -99,13 +283,209 @@ impl UI {
         }
     }
 
+    fn handle_manual_control_input(&mut self) {
+        if is_key_pressed(self.keyboard_controls.toggle_manual_mode) {
+            self.manual_commands.push(ManualCommand::ToggleManualMode);
+        }
+
+        let movements = [
+            (self.keyboard_controls.robot_up, Direction::Up),
+            (self.keyboard_controls.robot_down, Direction::Down),
+            (self.keyboard_controls.robot_left, Direction::Left),
+            (self.keyboard_controls.robot_right, Direction::Right),
+        ];
+        for (key, direction) in movements {
+            if is_key_pressed(key) {
+                self.robot_facing = direction.clone();
+                self.manual_commands.push(ManualCommand::Go(direction));
+            }
+        }
+
+        if is_key_pressed(self.keyboard_controls.robot_destroy) {
+            self.manual_commands.push(ManualCommand::Destroy(self.robot_facing.clone()));
+        }
+        if is_key_pressed(self.keyboard_controls.robot_put) {
+            self.manual_commands.push(ManualCommand::Put(self.robot_facing.clone(), self.put_content.clone()));
+        }
+        if is_key_pressed(self.keyboard_controls.robot_next_put_content) {
+            self.next_put_content_requested = true;
+        }
+        if is_key_pressed(self.keyboard_controls.robot_teleport) {
+            self.manual_commands.push(ManualCommand::Teleport(self.robot_facing.clone()));
+        }
+    }
+
+    pub(super) fn is_console_open(&self) -> bool {
+        self.console.is_open()
+    }
+
+    /// Decides if the robots play a tick in this frame, taking into account pauses and single steps
+    pub(crate) fn should_tick(&mut self, tick_due: bool) -> bool {
+        if self.pending_steps > 0 {
+            self.pending_steps -= 1;
+            true
+        } else {
+            !self.paused && tick_due
+        }
+    }
+
+    fn execute_console_command(&mut self, command: ConsoleCommand, props: &UIProps) {
+        match command {
+            ConsoleCommand::Pause => {
+                self.paused = true;
+                self.console.print("Paused");
+            }
+            ConsoleCommand::Resume => {
+                self.paused = false;
+                self.console.print("Resumed");
+            }
+            ConsoleCommand::Step(ticks) => {
+                self.paused = true;
+                self.pending_steps += ticks;
+                self.console.print(format!("Running {} ticks", ticks));
+            }
+            ConsoleCommand::Speed(seconds) => {
+                *self.tick_time.borrow_mut() = seconds;
+                self.console.print(format!("Game tick interval set to {} s", seconds));
+            }
+            ConsoleCommand::Goto(x, z) => {
+                self.camera_target = Some((x, z));
+                self.console.print(format!("Camera moved to X: {}, Y: {}", x, z));
+            }
+            ConsoleCommand::Find(name) => {
+                let target = SearchTarget::all()
+                    .into_iter()
+                    .find(|target| target.name().to_lowercase() == name);
+                let Some(target) = target else {
+                    self.console.print(format!("Unknown content or tile type \"{}\"", name));
+                    return;
+                };
+
+                let found = target.search(props.explored_world_map, props.robot_coordinates);
+                self.console.print(format!("Found {} tiles with {}", found.len(), target.name()));
+                for ((x, z), distance) in found.iter().take(20) {
+                    self.console.print(format!("  X: {}, Y: {} ({} tiles away)", x, z, distance));
+                }
+            }
+            ConsoleCommand::ExportMap(path) => {
+                self.export_map(props, &path);
+            }
+            ConsoleCommand::ExportModel(path) => {
+                self.export_model(props, &path);
+            }
+            ConsoleCommand::ExportData(prefix) => {
+                self.export_data(props, &prefix);
+            }
+            ConsoleCommand::ToggleGrid => {
+                self.show_grid = !self.show_grid;
+            }
+            ConsoleCommand::HudScale(scale) => {
+                self.layout.set_scale(scale);
+                self.console.print(format!("HUD scale set to {}", self.layout.scale()));
+            }
+            ConsoleCommand::HudReset => {
+                match self.layout.reset() {
+                    Ok(()) => self.console.print("Windows moved back to their default position"),
+                    Err(error) => self.console.print(error),
+                }
+            }
+        }
+    }
+
+    fn export_map(&mut self, props: &UIProps, path: &str) {
+        let robot_path = props.robot_path.iter().copied().collect::<Vec<_>>();
+        match export_map_png(props.explored_world_map, &robot_path, MAP_EXPORT_BLOCK_SIZE, path) {
+            Ok(()) => self.console.print(format!("Map exported to {}", path)),
+            Err(error) => self.console.print(error),
+        }
+    }
+
+    fn export_model(&mut self, props: &UIProps, path: &str) {
+        let robot_path = props.robot_path.iter().copied().collect::<Vec<_>>();
+        match export_map_obj(props.explored_world_map, &robot_path, path) {
+            Ok(()) => self.console.print(format!("Model exported to {}", path)),
+            Err(error) => self.console.print(error),
+        }
+    }
+
+    fn export_data(&mut self, props: &UIProps, prefix: &str) {
+        match export_run_data(props.robots[props.selected_robot].data, prefix) {
+            Ok(()) => self.console.print(format!("Run data exported to {}_run.json, {}_metrics.csv and {}_map.csv", prefix, prefix, prefix)),
+            Err(error) => self.console.print(error),
+        }
+    }
+
+    /// Chooses the content used by the put command of the manual control among the ones in the backpack of the focused robot.
+    ///
+    /// The choice is kept while that content is in the backpack, otherwise it goes back to the first content in alphabetical order,
+    /// the `robot_next_put_content` key moves it to the next one.
+    fn update_put_content(&mut self, props: &UIProps) {
+        let mut contents = props.robot_backpack_contents.iter()
+            .filter(|(_, amount)| **amount > 0)
+            .map(|(content, _)| content.clone())
+            .collect::<Vec<_>>();
+        contents.sort_by_key(|content| content_name(content));
+
+        let current = self.put_content.as_ref()
+            .and_then(|put_content| contents.iter().position(|content| content_name(content) == content_name(put_content)));
+        let index = match current {
+            Some(index) if self.next_put_content_requested => (index + 1) % contents.len(),
+            Some(index) => index,
+            None => 0,
+        };
+        self.next_put_content_requested = false;
+        self.put_content = contents.get(index).cloned();
+    }
+
+    /// Commands for the focused robot collected since the last call
+    pub(crate) fn take_manual_commands(&mut self) -> Vec<ManualCommand> {
+        std::mem::take(&mut self.manual_commands)
+    }
+
     fn map_range(x: f32, x_min: f32, x_max: f32, y_min: f32, y_max: f32) -> f32 {
         (x - x_min) * ((y_max - y_min) / (x_max - x_min)) + y_min
     }
 
+    /// The energy is unknown until the robot sends the first game info
+    fn is_energy_low(&self, props: &UIProps) -> bool {
+        props.ticks > 0 && self.alerts.is_energy_low(props.robot_energy, props.robot_max_energy)
+    }
+
+    /// Average energy consumed, recharged and lost per tick in the last ticks, `None` before the second tick
+    fn energy_rates(history: &VecDeque<TickMetrics>) -> Option<EnergyRates> {
+        let recent = history.range(history.len().saturating_sub(ENERGY_RATE_TICKS + 1)..).collect::<Vec<_>>();
+        let ticks = recent.len().checked_sub(1).filter(|ticks| *ticks > 0)? as f32;
+        let (first, last) = (recent.first()?, recent.last()?);
+
+        Some(EnergyRates {
+            consumed: recent[1..].iter().map(|metrics| metrics.energy_consumed).sum::<usize>() as f32 / ticks,
+            recharged: recent[1..].iter().map(|metrics| metrics.energy_recharged).sum::<usize>() as f32 / ticks,
+            drain: (first.robot_energy as f32 - last.robot_energy as f32) / ticks,
+        })
+    }
+
+    fn explored_tiles(explored_world_map: &[Vec<Option<Tile>>]) -> usize {
+        explored_world_map.iter().flatten().filter(|tile| tile.is_some()).count()
+    }
+
+    fn explored_percentage(explored_world_map: &[Vec<Option<Tile>>]) -> f32 {
+        let total = explored_world_map.iter().map(|row| row.len()).sum::<usize>();
+        let explored = Self::explored_tiles(explored_world_map);
+
+        if total == 0 {
+            0.0
+        } else {
+            explored as f32 / total as f32 * 100.0
+        }
+    }
+
     fn show_game_info(&mut self, props: &UIProps) {
-        let position = vec2(self.viewport_width - 400.0, 0.0);
-        let size = vec2(400.0, 700.0);
+        let (position, size) = self.layout.place("game_info", Anchor::TopRight, Vec2::ZERO, vec2(400.0, 700.0));
+        let scale = self.layout.scale();
+        if props.ticks != self.backpack_flash_tick {
+            self.backpack_flash_tick = props.ticks;
+            self.backpack_flash_start = get_time();
+        }
         
         widgets::Window::new(
             hash!("game_info_window"),
@@ -114,51 +494,135 @@ impl UI {
         )
         .label("Robot")
         .titlebar(true)
+        .movable(false)
         .ui(&mut *root_ui(), |ui| {
+            if props.robots.len() > 1 {
+                let names = props.robots.iter().map(|robot| robot.name).collect::<Vec<_>>();
+                ui.combo_box(hash!("robot_selector"), "Focused robot", &names, &mut self.selected_robot);
+                for robot in props.robots.iter() {
+                    let cursor = ui.canvas().cursor();
+                    ui.canvas().rect(Rect::new(cursor.x, cursor.y + 4.0 * scale, 12.0 * scale, 12.0 * scale), BLACK, robot.color);
+                    ui.same_line(20.0 * scale);
+                    ui.label(None, &format!("{} - energy: {}, score: {}", robot.name, robot.data.robot_energy, robot.data.robot_score));
+                }
+                ui.separator();
+            }
             ui.label(None, format!("Game tick interval: ").as_str());
             ui.slider(hash!("tick_time_slider"), "[0.0 - 5.0]", 0.0..5.0, &mut self.tick_time.borrow_mut());
             ui.checkbox(hash!("daylight_cicle_checkbox"), "Show daylight cycle", &mut self.daylight_cycle);
-            ui.label(None, "Energy: ");
-            let max_energy_level = 1000.0; //const MAX_ENERGY_LEVEL: usize = 1000;
-            let cursor = ui.canvas().cursor();
+            ui.checkbox(hash!("fog_of_war_checkbox"), "Show fog of war", &mut self.fog_of_war);
+            ui.checkbox(hash!("content_labels_checkbox"), "Show content amounts", &mut self.content_labels);
+            ui.label(None, format!("World view: {}", match self.world_view {
+                WorldView::Explored => "Explored",
+                WorldView::GroundTruth => "True world",
+                WorldView::GroundTruthDimmed => "True world (unexplored dimmed)",
+            }).as_str());
+            let low_energy = self.is_energy_low(props);
+            ui.label(None, &format!("Energy: {}/{}", props.robot_energy, props.robot_max_energy));
+            let bar_size = vec2(300.0, 20.0) * scale;
+            let origin = ui.canvas().request_space(bar_size);
+            ui.canvas().rect(Rect::new(origin.x, origin.y, bar_size.x, bar_size.y), BLACK, Color::new(0.0, 0.0, 0.0, 0.3));
             ui.canvas().rect(
                 Rect::new(
-                    cursor.x,
-                    cursor.y,
-                    Self::map_range(props.robot_energy as f32, 0.0, max_energy_level, 1.0, 300.0),
-                    20.0
+                    origin.x,
+                    origin.y,
+                    Self::map_range(props.robot_energy as f32, 0.0, props.robot_max_energy.max(1) as f32, 1.0, bar_size.x).min(bar_size.x),
+                    bar_size.y
                 ),
                 BLACK,
-                YELLOW,
+                if low_energy { RED } else { YELLOW },
             );
+            match Self::energy_rates(props.history) {
+                Some(rates) => {
+                    // The rates come from the energy events, that are there only if the robot forwards them to the channel
+                    if rates.consumed > 0.0 || rates.recharged > 0.0 {
+                        ui.label(None, &format!("Consumed: {:.1}/tick, recharged: {:.1}/tick", rates.consumed, rates.recharged));
+                    }
+                    if rates.drain > 0.0 {
+                        ui.label(None, &format!("Empty in about {} ticks ({:.1}/tick)", (props.robot_energy as f32 / rates.drain).ceil(), -rates.drain));
+                    } else {
+                        ui.label(None, &format!("Not draining ({:+.1}/tick)", -rates.drain));
+                    }
+                }
+                None => ui.label(None, "Energy rate: waiting for the first ticks"),
+            }
+            if low_energy {
+                ui.label(None, "Low energy!");
+            }
             ui.separator();
 
+            if props.manual_control_available {
+                ui.label(None, &format!("Manual control: {}", if props.manual_mode { "on" } else { "off" }));
+                if props.manual_mode {
+                    ui.label(None, &format!(
+                        "Content to put: {} ({:?} for the next one)",
+                        self.put_content.as_ref().map_or("none", content_name),
+                        self.keyboard_controls.robot_next_put_content
+                    ));
+                }
+            }
             ui.label(None, &format!("Coordinates X: {}, Y: {}", props.robot_coordinates.0, props.robot_coordinates.1));
-            ui.label(None, &format!("Backpack size: {}", props.robot_backpack_size));
+            let used = props.robot_backpack_contents.values().sum::<usize>();
+            ui.label(None, &format!("Backpack: {}/{}", used, props.robot_backpack_size));
+            let bar_size = vec2(300.0, 20.0) * scale;
+            let fill = if props.robot_backpack_size == 0 { 0.0 } else { (used as f32 / props.robot_backpack_size as f32).min(1.0) };
+            let origin = ui.canvas().request_space(bar_size);
+            ui.canvas().rect(Rect::new(origin.x, origin.y, bar_size.x, bar_size.y), BLACK, Color::new(0.0, 0.0, 0.0, 0.3));
+            ui.canvas().rect(
+                Rect::new(origin.x, origin.y, bar_size.x * fill, bar_size.y),
+                BLACK,
+                if fill >= 1.0 { RED } else if fill >= 0.8 { ORANGE } else { GREEN }
+            );
 
-            let backpack_size = vec2(390.0, 400.0);
-            let backpack_item_size = vec2(380.0, 22.0);
+            // Every content is listed in the same place at every tick, the ones changed in the last tick flash
+            let mut items = props.robot_backpack_contents.iter()
+                .map(|(content, amount)| (content_name(content), content, *amount))
+                .collect::<Vec<_>>();
+            items.sort_by_key(|(name, _, _)| *name);
+            let flash = (1.0 - (get_time() - self.backpack_flash_start) / BACKPACK_FLASH_DURATION).clamp(0.0, 1.0) as f32;
+
+            let backpack_size = vec2(390.0, 380.0) * scale;
+            let backpack_item_size = vec2(380.0, 24.0) * scale;
+            let icon_size = 20.0 * scale;
             widgets::Group::new(
                 hash!("backpack_contents"),
                 backpack_size
             )
             .layout(Layout::Vertical)
             .ui(ui, |ui| {
-                for (index, (item, amount)) in props.robot_backpack_contents.iter().enumerate() {
+                for (index, (name, content, amount)) in items.into_iter().enumerate() {
                     widgets::Group::new(
-                        hash!("backpack_item", index, amount),
+                        hash!("backpack_item", index),
                         backpack_item_size
                     )
                     .layout(Layout::Horizontal)
-                    .ui(ui, |ui| {  
-                        ui.label(None, &format!("{}", item));
-                        ui.same_line(150.0);
+                    .ui(ui, |ui| {
+                        let change = props.backpack_changes.get(name).copied().unwrap_or(0);
+                        if change != 0 {
+                            let cursor = ui.canvas().cursor();
+                            let color = if change > 0 { GREEN } else { RED };
+                            ui.canvas().rect(
+                                Rect::new(cursor.x, cursor.y, backpack_item_size.x, backpack_item_size.y),
+                                Color::new(0.0, 0.0, 0.0, 0.0),
+                                Color::new(color.r, color.g, color.b, 0.6 * flash)
+                            );
+                        }
+                        if let Some(icon) = props.content_icons.get(name) {
+                            ui.texture(icon.clone(), icon_size, icon_size);
+                        }
+                        ui.same_line(30.0 * scale);
+                        ui.label(None, &format!("{}", content));
+                        ui.same_line(180.0 * scale);
                         ui.label(None, &format!("{}", amount));
+                        if change != 0 {
+                            ui.same_line(240.0 * scale);
+                            ui.label(None, &format!("{:+}", change));
+                        }
                     });
                 }
             });
 
-            ui.label(None, format!("Discoverable tiles: {}", props.discoverable_tiles).as_str());
+            ui.label(None, format!("Discoverable tiles: {} ({:.1}% explored)", props.discoverable_tiles, Self::explored_percentage(props.explored_world_map)).as_str());
             ui.label(None, format!("Score: {}", props.robot_score).as_str());
             ui.label(None, format!("Time of day: {:?}", props.time_of_day).as_str());
             ui.label(None, format!("Time clock: {}", props.time_of_day_string).as_str());
@@ -170,12 +634,12 @@ impl UI {
                 WeatherType::TrentinoSnow => "Trentino's snow",
                 }).as_str()
             );
+            ui.texture(self.weather_icons.get(props.weather_condition).clone(), 48.0, 48.0);
         });
     }
 
-    fn show_stats(&self) {
-        let position = vec2(0.0, 0.0);
-        let size = vec2(200.0, 200.0);
+    fn show_stats(&mut self) {
+        let (position, size) = self.layout.place("stats", Anchor::TopLeft, Vec2::ZERO, vec2(200.0, 200.0));
 
         widgets::Window::new(
             hash!("stats_window"), 
@@ -184,6 +648,7 @@ impl UI {
         )
         .label("Statistics")
         .titlebar(true)
+        .movable(false)
         .ui(&mut *root_ui(), |ui| {
             ui.label(None, "--- MACROQUAD ---");
             ui.label(None, &format!("FPS: {}", get_fps()));
@@ -207,9 +672,8 @@ impl UI {
         });
     }
 
-    fn show_help(&self) {
-        let position = vec2(0.0, self.viewport_height - 200.0);
-        let size = vec2(300.0, 200.0);
+    fn show_help(&mut self) {
+        let (position, size) = self.layout.place("help", Anchor::BottomLeft, Vec2::ZERO, vec2(460.0, 510.0));
 
         widgets::Window::new(
             hash!("help_window"), 
@@ -218,20 +682,305 @@ impl UI {
         )
         .label("Help")
         .titlebar(true)
+        .movable(false)
         .ui(&mut *root_ui(), |ui| {
-            ui.label(None, &format!("WASD + mouse to move"));
-            ui.label(None, &format!("Toggle mouse grab: G"));
-            ui.label(None, &format!("Toggle tile info window: I"));
-            ui.label(None, &format!("Toggle statistics window: F3"));
+            let keys = &self.keyboard_controls;
+            ui.label(None, &format!(
+                "{:?}{:?}{:?}{:?} + mouse to move, {:?}/{:?} up and down",
+                keys.move_forward, keys.move_left, keys.move_backward, keys.move_right, keys.move_up, keys.move_down
+            ));
+            ui.label(None, &format!("Toggle mouse grab: {:?}", keys.toggle_free_mouse));
+            ui.label(None, &format!("Toggle tile info window: {:?}", keys.toggle_tile_info));
+            ui.label(None, &format!("Toggle statistics window: {:?}", keys.toggle_statistics));
+            ui.label(None, &format!("Toggle teleports window: {:?}", keys.toggle_teleports));
+            ui.label(None, &format!("Focus next robot: {:?}", keys.next_robot));
+            ui.label(None, &format!("Cycle world view: {:?}", keys.cycle_world_view));
+            ui.label(None, &format!("Toggle event log: {:?}", keys.toggle_event_log));
+            ui.label(None, &format!("Toggle debug drawings window: {:?}", keys.toggle_debug));
+            ui.label(None, &format!("Toggle telemetry window: {:?}", keys.toggle_telemetry));
+            ui.label(None, &format!("Toggle console: {:?}", keys.toggle_console));
+            ui.label(None, &format!("Toggle search window: {:?}", keys.toggle_search));
+            ui.label(None, &format!("Export explored map to PNG: {:?}", keys.export_map));
+            ui.label(None, &format!("Export run data to JSON and CSV: {:?}", keys.export_data));
+            ui.label(None, &format!("Export explored map to OBJ: {:?}", keys.export_model));
+            ui.label(None, &format!("Toggle manual control: {:?}", keys.toggle_manual_mode));
+            ui.label(None, &format!(
+                "Manual control: {:?}/{:?}/{:?}/{:?} to move",
+                keys.robot_up, keys.robot_down, keys.robot_left, keys.robot_right
+            ));
+            ui.label(None, &format!(
+                "{:?} destroy, {:?} put, {:?} teleport",
+                keys.robot_destroy, keys.robot_put, keys.robot_teleport
+            ));
+            ui.label(None, &format!("Next content to put: {:?}", keys.robot_next_put_content));
             //ui.label(None, &format!("WIP - Take screenshot: F2"));
-            ui.label(None, &format!("Camera mode: C"));
-            ui.label(None, &format!("Exit: Esc"));
+            ui.label(None, &format!("Toggle HUD: {:?}", keys.toggle_hud));
+            ui.label(None, &format!("Follow the robot with the camera: {:?}", keys.cycle_camera_mode));
+            ui.label(None, &format!("Move a window: drag its title bar"));
+            ui.label(None, &format!("Exit: {:?}", keys.exit));
+        });
+    }
+
+    fn show_teleports(&mut self, props: &UIProps) {
+        let (position, size) = self.layout.place("teleports", Anchor::TopLeft, vec2(0.0, 220.0), vec2(300.0, 250.0));
+
+        widgets::Window::new(
+            hash!("teleports_window"),
+            position,
+            size
+        )
+        .label("Teleports")
+        .titlebar(true)
+        .movable(false)
+        .ui(&mut *root_ui(), |ui| {
+            for (x, row) in props.explored_world_map.iter().enumerate() {
+                for (z, tile) in row.iter().enumerate() {
+                    if let Some(tile) = tile {
+                        if let TileType::Teleport(activated) = tile.tile_type {
+                            let status = if activated { "active" } else { "inactive" };
+                            if ui.button(None, format!("X: {}, Y: {} ({})", x, z, status).as_str()) {
+                                self.camera_target = Some((x, z));
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    pub(crate) fn selected_robot(&self) -> usize {
+        self.selected_robot
+    }
+
+    /// Tile the user asked the camera to move to, if any
+    pub(super) fn take_camera_target(&mut self) -> Option<(usize, usize)> {
+        self.camera_target.take()
+    }
+
+    fn show_comparison(&mut self, props: &UIProps) {
+        let (left, right) = (&props.robots[0], &props.robots[1]);
+        let (position, size) = self.layout.place("comparison", Anchor::Bottom, Vec2::ZERO, vec2(400.0, 300.0));
+        let scale = self.layout.scale();
+
+        widgets::Window::new(
+            hash!("comparison_window"),
+            position,
+            size
+        )
+        .label("Comparison")
+        .titlebar(true)
+        .movable(false)
+        .ui(&mut *root_ui(), |ui| {
+            let row = |ui: &mut macroquad::ui::Ui, name: &str, left_value: f32, right_value: f32| {
+                ui.label(None, name);
+                ui.same_line(140.0 * scale);
+                ui.label(None, &format!("{}", left_value));
+                ui.same_line(220.0 * scale);
+                ui.label(None, &format!("{}", right_value));
+                ui.same_line(300.0 * scale);
+                ui.label(None, &format!("{:+}", right_value - left_value));
+            };
+
+            ui.label(None, "");
+            ui.same_line(140.0 * scale);
+            ui.label(None, left.name);
+            ui.same_line(220.0 * scale);
+            ui.label(None, right.name);
+            ui.same_line(300.0 * scale);
+            ui.label(None, "Diff");
+            ui.separator();
+
+            row(ui, "Score", left.data.robot_score, right.data.robot_score);
+            row(ui, "Energy", left.data.robot_energy as f32, right.data.robot_energy as f32);
+            row(
+                ui,
+                "Explored tiles",
+                Self::explored_tiles(&left.data.explored_world_map) as f32,
+                Self::explored_tiles(&right.data.explored_world_map) as f32
+            );
+
+            ui.separator();
+            let mut contents = left.data.robot_backpack_contents.keys()
+                .chain(right.data.robot_backpack_contents.keys())
+                .map(|content| content.to_string())
+                .collect::<Vec<_>>();
+            contents.sort();
+            contents.dedup();
+            for content in contents {
+                let amount = |backpack: &HashMap<Content, usize>| {
+                    backpack.iter()
+                        .filter(|(item, _)| item.to_string() == content)
+                        .map(|(_, amount)| *amount)
+                        .sum::<usize>() as f32
+                };
+                row(ui, &content, amount(&left.data.robot_backpack_contents), amount(&right.data.robot_backpack_contents));
+            }
+        });
+    }
+
+    fn show_search(&mut self, props: &UIProps) {
+        let (position, size) = self.layout.place("search", Anchor::TopRight, vec2(-410.0, 0.0), vec2(290.0, 400.0));
+        let targets = SearchTarget::all();
+        let labels = std::iter::once("None".to_string())
+            .chain(targets.iter().map(|target| target.label()))
+            .collect::<Vec<_>>();
+
+        widgets::Window::new(
+            hash!("search_window"),
+            position,
+            size
+        )
+        .label("Search")
+        .titlebar(true)
+        .movable(false)
+        .ui(&mut *root_ui(), |ui| {
+            let labels = labels.iter().map(|label| label.as_str()).collect::<Vec<_>>();
+            ui.combo_box(hash!("search_target"), "Find", &labels, &mut self.search_target);
+
+            let key = (self.search_target, props.selected_robot, props.ticks);
+            if self.search_key != Some(key) {
+                self.search_key = Some(key);
+                self.search_results = match self.search_target.checked_sub(1).and_then(|index| targets.get(index)) {
+                    Some(target) => target.search(props.explored_world_map, props.robot_coordinates),
+                    None => Vec::new(),
+                };
+            }
+
+            ui.label(None, &format!("{} tiles found", self.search_results.len()));
+            ui.separator();
+            for ((x, z), distance) in self.search_results.iter() {
+                if ui.button(None, format!("X: {}, Y: {} - {} tiles away", x, z, distance).as_str()) {
+                    self.camera_target = Some((*x, *z));
+                }
+            }
+        });
+    }
+
+    /// Tiles matching the current search, highlighted by the renderer
+    pub(super) fn get_search_results(&self) -> Vec<(usize, usize)> {
+        if self.show_search {
+            self.search_results.iter().map(|(coordinates, _)| *coordinates).collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn show_event_log(&mut self, props: &UIProps) {
+        let (position, size) = self.layout.place("event_log", Anchor::TopLeft, vec2(0.0, 480.0), vec2(400.0, 250.0));
+
+        widgets::Window::new(
+            hash!("event_log_window"),
+            position,
+            size
+        )
+        .label("Event log")
+        .titlebar(true)
+        .movable(false)
+        .ui(&mut *root_ui(), |ui| {
+            for entry in props.event_log.iter().rev() {
+                ui.label(None, entry);
+            }
+        });
+    }
+
+    fn show_debug(&mut self, props: &UIProps) {
+        let (position, size) = self.layout.place("debug", Anchor::TopRight, vec2(0.0, 480.0), vec2(350.0, 250.0));
+
+        widgets::Window::new(
+            hash!("debug_window"),
+            position,
+            size
+        )
+        .label("Debug")
+        .titlebar(true)
+        .movable(false)
+        .ui(&mut *root_ui(), |ui| {
+            if props.debug.is_empty() {
+                ui.label(None, "Nothing drawn by the robot in the last tick");
+                return;
+            }
+
+            for marker in props.debug.markers.iter() {
+                ui.label(None, &format!("Marker {} - X: {}, Y: {}", marker.label, marker.coordinates.0, marker.coordinates.1));
+            }
+            for path in props.debug.paths.iter() {
+                match (path.first(), path.last()) {
+                    (Some(first), Some(last)) => ui.label(None, &format!(
+                        "Path of {} tiles - from X: {}, Y: {} to X: {}, Y: {}",
+                        path.len(), first.0, first.1, last.0, last.1
+                    )),
+                    _ => ui.label(None, "Empty path"),
+                }
+            }
+            if !props.debug.highlighted_tiles.is_empty() {
+                ui.label(None, &format!("{} highlighted tiles", props.debug.highlighted_tiles.len()));
+            }
+            ui.separator();
+            for message in props.debug.messages.iter() {
+                ui.label(None, message);
+            }
+        });
+    }
+
+    fn show_telemetry(&mut self, props: &UIProps) {
+        let (position, size) = self.layout.place("telemetry", Anchor::BottomRight, Vec2::ZERO, vec2(400.0, 350.0));
+        let chart_size = TELEMETRY_CHART_SIZE * self.layout.scale();
+        let custom_series = props.history.iter()
+            .flat_map(|metrics| metrics.custom.keys())
+            .map(|key| key.as_str())
+            .collect::<BTreeSet<_>>();
+        let series = BUILT_IN_SERIES.iter().copied().chain(custom_series).collect::<Vec<_>>();
+        self.telemetry_series = self.telemetry_series.min(series.len() - 1);
+
+        widgets::Window::new(
+            hash!("telemetry_window"),
+            position,
+            size
+        )
+        .label("Telemetry")
+        .titlebar(true)
+        .movable(false)
+        .ui(&mut *root_ui(), |ui| {
+            if props.custom_values.is_empty() {
+                ui.label(None, "The robot didn't send custom values");
+            }
+            for (key, value) in props.custom_values.iter() {
+                ui.label(None, &format!("{}: {}", key, value));
+            }
+            ui.separator();
+
+            ui.combo_box(hash!("telemetry_series"), "Chart", &series, &mut self.telemetry_series);
+            let name = series[self.telemetry_series];
+            let start = props.history.len().saturating_sub(TELEMETRY_CHART_TICKS);
+            let points = props.history.range(start..)
+                .filter_map(|metrics| series_value(metrics, name).map(|value| (metrics.tick, value)))
+                .collect::<Vec<_>>();
+            let min = points.iter().map(|(_, value)| *value).fold(f64::INFINITY, f64::min);
+            let max = points.iter().map(|(_, value)| *value).fold(f64::NEG_INFINITY, f64::max);
+
+            let origin = ui.canvas().request_space(chart_size);
+            ui.canvas().rect(Rect::new(origin.x, origin.y, chart_size.x, chart_size.y), GRAY, Color::new(0.0, 0.0, 0.0, 0.3));
+            let first_tick = points.first().map_or(0, |(tick, _)| *tick);
+            let ticks = points.last().map_or(1, |(tick, _)| (tick - first_tick).max(1));
+            let range = (max - min).max(f64::EPSILON);
+            let point = |(tick, value): (usize, f64)| vec2(
+                origin.x + (tick - first_tick) as f32 / ticks as f32 * chart_size.x,
+                origin.y + chart_size.y - ((value - min) / range) as f32 * chart_size.y
+            );
+            for step in points.windows(2) {
+                ui.canvas().line(point(step[0]), point(step[1]), GREEN);
+            }
+
+            match points.last() {
+                Some((_, last)) => ui.label(None, &format!("{} - last: {}, min: {}, max: {}", name, last, min, max)),
+                None => ui.label(None, &format!("{} - no values yet", name)),
+            }
         });
     }
 
     fn show_exit_dialog(&mut self) {
-        let position = vec2(self.viewport_width / 2.0 - 100.0, self.viewport_height / 2.0 - 50.0);
-        let size = vec2(200.0, 100.0);
+        let (position, size) = self.layout.place("exit_dialog", Anchor::Center, Vec2::ZERO, vec2(200.0, 100.0));
+        let scale = self.layout.scale();
 
         widgets::Window::new(
             hash!("exit_dialog"),
@@ -240,14 +989,15 @@ impl UI {
         )
         .label("Exit")
         .titlebar(true)
+        .movable(false)
         .ui(&mut *root_ui(), |ui| {
             ui.label(None, "Do you really want to quit?");
             ui.separator();
-            ui.same_line(60.0);
+            ui.same_line(60.0 * scale);
             if ui.button(None, "Yes") {
                 self.exit = true;
             }
-            ui.same_line(120.);
+            ui.same_line(120.0 * scale);
             if ui.button(None, "No") {
                 if self.old_grab_status {
                     self.toggle_mouse_grab();
@@ -258,9 +1008,8 @@ impl UI {
         });
     }
 
-    fn show_tile_info(&self, props: &UIProps) {
-        let position = vec2(self.viewport_width / 2.0 - 150.0, 0.0);
-        let size = vec2(300.0, 100.0);
+    fn show_tile_info(&mut self, props: &UIProps) {
+        let (position, size) = self.layout.place("tile_info", Anchor::Top, Vec2::ZERO, vec2(300.0, 100.0));
 
         widgets::Window::new(
             hash!("tile_info_window"), 
@@ -269,10 +1018,11 @@ impl UI {
         )
         .label("Tile")
         .titlebar(true)
+        .movable(false)
         .ui(&mut *root_ui(), |ui| {
             let (x, z) = props.robot_coordinates;
 
-            if let Some(tile) = &props.explored_world_map[x][z] {
+            if let Some(tile) = props.explored_world_map.get(x).and_then(|row| row.get(z)).and_then(|tile| tile.as_ref()) {
                 ui.label(None, format!("Tile type: {:?}", tile.tile_type).as_str());
                 ui.label(None, format!("Tile type: {}", tile.content).as_str());
             }
@@ -287,8 +1037,53 @@ impl UI {
         self.daylight_cycle
     }
 
+    pub(super) fn is_fog_of_war_on(&self) -> bool {
+        self.fog_of_war
+    }
+
+    pub(super) fn are_content_labels_on(&self) -> bool {
+        self.content_labels
+    }
+
+    pub(super) fn is_grid_on(&self) -> bool {
+        self.show_grid
+    }
+
+    pub(super) fn get_world_view(&self) -> WorldView {
+        self.world_view
+    }
+
+    /// Work done every frame, even when the HUD is hidden.
+    ///
+    /// The console is drawn here too, it takes the keyboard while it's open so it must always be visible.
+    pub(super) fn update(&mut self, props: &UIProps) {
+        self.update_put_content(props);
+        if self.alerts.update(props) {
+            self.paused = true;
+            self.console.print("Paused by an alert, type resume to continue");
+        }
+        if let Some(command) = self.console.render() {
+            self.execute_console_command(command, props);
+        }
+    }
+
     pub(super) fn render(&mut self, props: UIProps) {
-        draw_text("Press H for help", 0.0, self.viewport_height - 80.0, 30.0, GREEN);
+        self.robots_count = props.robots.len();
+        self.selected_robot = props.selected_robot;
+
+        if let Err(error) = self.layout.handle_drag(!self.is_mouse_grabbed) {
+            self.console.print(error);
+        }
+
+        let scale = self.layout.scale();
+        draw_text("Press H for help", 0.0, screen_height() - 80.0 * scale, 30.0 * scale, GREEN);
+        if self.paused {
+            draw_text("PAUSED", 0.0, screen_height() - 110.0 * scale, 30.0 * scale, YELLOW);
+        }
+        if self.is_energy_low(&props) && get_time().fract() < 0.5 {
+            draw_text("LOW ENERGY", 0.0, screen_height() - 140.0 * scale, 30.0 * scale, RED);
+        }
+        self.alerts.render(scale);
 
         self.show_game_info(&props);
         
@@ -301,6 +1096,41 @@ impl UI {
         if self.show_help {
             self.show_help();
         }
+        if self.show_teleports {
+            self.show_teleports(&props);
+        }
+        if self.show_event_log {
+            self.show_event_log(&props);
+        }
+        if self.show_debug {
+            self.show_debug(&props);
+        }
+        if self.show_telemetry {
+            self.show_telemetry(&props);
+        }
+        if self.show_search {
+            self.show_search(&props);
+        }
+        if props.comparison && props.robots.len() >= 2 {
+            self.show_comparison(&props);
+        }
+        if self.export_requested || self.export_data_requested || self.export_model_requested {
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs());
+            if self.export_requested {
+                self.export_map(&props, &format!("explored_map_{}.png", timestamp));
+            }
+            if self.export_data_requested {
+                self.export_data(&props, &format!("run_{}", timestamp));
+            }
+            if self.export_model_requested {
+                self.export_model(&props, &format!("explored_map_{}.obj", timestamp));
+            }
+            self.export_requested = false;
+            self.export_data_requested = false;
+            self.export_model_requested = false;
+        }
         if self.quit_requested {
             if self.mouse_grabbed_flag {
                 self.mouse_grabbed_flag = false;
@@ -312,4 +1142,50 @@ impl UI {
             self.show_exit_dialog();
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::{ENERGY_RATE_TICKS, UI};
+    use crate::channel::TickMetrics;
+    use crate::test_fixtures::metrics;
+
+    fn energy_metrics(robot_energy: usize, energy_consumed: usize, energy_recharged: usize) -> TickMetrics {
+        TickMetrics {
+            robot_energy,
+            energy_consumed,
+            energy_recharged,
+            ..metrics(0)
+        }
+    }
+
+    #[test]
+    fn energy_rates_need_two_ticks() {
+        assert!(UI::energy_rates(&VecDeque::new()).is_none());
+        assert!(UI::energy_rates(&VecDeque::from([energy_metrics(100, 0, 0)])).is_none());
+    }
+
+    #[test]
+    fn energy_rates_are_averaged_per_tick() {
+        // The events of the first tick happened before the energy it recorded
+        let rates = UI::energy_rates(&VecDeque::from([energy_metrics(100, 50, 50), energy_metrics(90, 10, 0), energy_metrics(85, 8, 3)])).unwrap();
+
+        assert_eq!(rates.consumed, 9.0);
+        assert_eq!(rates.recharged, 1.5);
+        assert_eq!(rates.drain, 7.5);
+    }
+
+    #[test]
+    fn energy_rates_use_only_the_last_ticks() {
+        let mut history = (0..30).map(|tick| energy_metrics(1000 - tick * 2, 2, 0)).collect::<VecDeque<_>>();
+        history[30 - ENERGY_RATE_TICKS - 1].energy_consumed = 100;
+        history.push_back(energy_metrics(942, 2, 4));
+
+        let rates = UI::energy_rates(&history).unwrap();
+        assert_eq!(rates.consumed, 2.0);
+        assert_eq!(rates.recharged, 4.0 / ENERGY_RATE_TICKS as f32);
+        assert_eq!(rates.drain, (history[10].robot_energy - 942) as f32 / ENERGY_RATE_TICKS as f32);
+    }
+}
@@ -0,0 +1,118 @@
+use macroquad::prelude::*;
+use macroquad::rand::gen_range;
+use robotics_lib::world::environmental_conditions::WeatherType;
+
+struct Particle {
+    position: Vec3,
+    velocity: Vec3,
+}
+
+struct WeatherSettings {
+    particles_count: usize,
+    fall_speed: f32,
+    wind: Vec3,
+    color: Color,
+    is_snow: bool,
+}
+
+pub(super) struct WeatherEffects {
+    particles: Vec<Particle>,
+    spawn_radius: f32,
+    spawn_height: f32,
+}
+
+impl WeatherEffects {
+    fn settings(weather_condition: WeatherType) -> Option<WeatherSettings> {
+        match weather_condition {
+            WeatherType::Rainy => Some(WeatherSettings {
+                particles_count: 1500,
+                fall_speed: 25.0,
+                wind: vec3(1.0, 0.0, 0.5),
+                color: Color::new(0.6, 0.7, 0.9, 0.6),
+                is_snow: false,
+            }),
+            WeatherType::TropicalMonsoon => Some(WeatherSettings {
+                particles_count: 4000,
+                fall_speed: 40.0,
+                wind: vec3(8.0, 0.0, 4.0),
+                color: Color::new(0.5, 0.6, 0.8, 0.7),
+                is_snow: false,
+            }),
+            WeatherType::TrentinoSnow => Some(WeatherSettings {
+                particles_count: 2000,
+                fall_speed: 3.0,
+                wind: vec3(0.5, 0.0, 0.3),
+                color: WHITE,
+                is_snow: true,
+            }),
+            WeatherType::Sunny | WeatherType::Foggy => None,
+        }
+    }
+
+    fn spawn_particle(&self, center: Vec3, fall_speed: f32, wind: Vec3, anywhere: bool) -> Particle {
+        let height = if anywhere { gen_range(-self.spawn_height, self.spawn_height) } else { self.spawn_height };
+
+        Particle {
+            position: center + vec3(
+                gen_range(-self.spawn_radius, self.spawn_radius),
+                height,
+                gen_range(-self.spawn_radius, self.spawn_radius)
+            ),
+            velocity: wind - vec3(0.0, fall_speed * gen_range(0.8, 1.2), 0.0),
+        }
+    }
+
+    pub(super) fn update(&mut self, weather_condition: WeatherType, camera_position: Vec3) {
+        let Some(settings) = Self::settings(weather_condition) else {
+            self.particles.clear();
+            return;
+        };
+
+        let delta = get_frame_time();
+        self.particles.truncate(settings.particles_count);
+        while self.particles.len() < settings.particles_count {
+            let particle = self.spawn_particle(camera_position, settings.fall_speed, settings.wind, true);
+            self.particles.push(particle);
+        }
+
+        for index in 0..self.particles.len() {
+            let mut position = self.particles[index].position + self.particles[index].velocity * delta;
+            if settings.is_snow {
+                // Snowflakes sway a little instead of falling straight down
+                position.x += (get_time() as f32 + index as f32).sin() * delta * 0.5;
+            }
+
+            let offset = position - camera_position;
+            if offset.y < -self.spawn_height || offset.x.abs() > self.spawn_radius || offset.z.abs() > self.spawn_radius {
+                self.particles[index] = self.spawn_particle(camera_position, settings.fall_speed, settings.wind, false);
+            } else {
+                self.particles[index].position = position;
+            }
+        }
+    }
+
+    pub(super) fn render(&self, weather_condition: WeatherType) {
+        let Some(settings) = Self::settings(weather_condition) else {
+            return;
+        };
+
+        for particle in self.particles.iter() {
+            if settings.is_snow {
+                draw_cube(particle.position, vec3(0.08, 0.08, 0.08), None, settings.color);
+            } else {
+                let streak = particle.velocity.normalize() * 0.6;
+                draw_line_3d(particle.position, particle.position + streak, settings.color);
+            }
+        }
+    }
+}
+
+impl Default for WeatherEffects {
+    fn default() -> Self {
+        Self {
+            particles: Vec::new(),
+            spawn_radius: 30.0,
+            spawn_height: 20.0,
+        }
+    }
+}
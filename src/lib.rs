@@ -1,35 +1,195 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, io::Read, rc::Rc};
 
 use channel::Channel;
 use macroquad::prelude::*;
-use robotics_lib::{runner::Runnable, world::world_generator::Generator};
-use gui::GUI;
-use runner_wrapper::RunnerWrapper;
+use robotics_lib::{runner::Runnable, world::{tile::Tile, world_generator::Generator}};
+use gui::{GUI, GUISettings, RobotView};
+use plugin::OlympusPlugin;
+use runner_wrapper::{ReplayGenerator, RunnerWrapper};
+use stream::Subscriber;
 
+mod builder;
 mod gui;
 mod runner_wrapper;
+mod world_info;
 pub mod channel;
+pub mod export;
+pub mod manual_control;
+pub mod plugin;
+pub mod stream;
+#[cfg(test)]
+mod test_fixtures;
+
+pub use builder::VisualizerBuilder;
+pub use gui::alerts::AlertSettings;
+pub use gui::custom_camera::CameraMode;
+pub use gui::keyboard_controls::KeyboardControls;
+
+/// Remote robots have no generated world to show
+static NO_GROUND_TRUTH: Vec<Vec<Tile>> = Vec::new();
+
+/// Where the data shown for a robot comes from
+enum RobotSource {
+    Runner(RunnerWrapper),
+    Stream(Subscriber),
+}
+
+struct RobotEntry {
+    name: String,
+    color: Color,
+    source: RobotSource,
+    channel: Rc<RefCell<Channel>>,
+}
+
+impl RobotEntry {
+    fn get_ground_truth(&self) -> &Vec<Vec<Tile>> {
+        match &self.source {
+            RobotSource::Runner(runner) => runner.get_ground_truth(),
+            RobotSource::Stream(_) => &NO_GROUND_TRUTH,
+        }
+    }
+}
 
 pub struct Visualizer {
-    runner: RunnerWrapper,
+    robots: Vec<RobotEntry>,
     gui: GUI,
-    channel: Rc<RefCell<Channel>>,
+    tick_time: Rc<RefCell<f32>>,
+    export_on_exit: Option<String>,
+    grab_mouse: bool,
+    plugins: Vec<Box<dyn OlympusPlugin>>,
+    notified_ticks: usize,
 }
 
 impl Visualizer {
-    pub fn new(robot: Box<dyn Runnable> , world_generator: impl Generator, world_size: usize, channel: Rc<RefCell<Channel>>) -> Self {        
-        let tick_time = Rc::new(RefCell::new(0.5));
-        
+    /// Creates a visualizer with the default settings, use [`VisualizerBuilder`] to change them
+    ///
+    /// The size of the world is taken from the one created by the generator, it doesn't need to be square.
+    pub fn new(robot: Box<dyn Runnable> , world_generator: impl Generator, channel: Rc<RefCell<Channel>>) -> Self {
+        VisualizerBuilder::new().build(robot, world_generator, channel)
+    }
+
+    fn empty(tick_time: Rc<RefCell<f32>>, settings: GUISettings, grab_mouse: bool) -> Self {
         Self {
-            runner: RunnerWrapper::new(robot, world_generator, Rc::clone(&tick_time)),
-            gui: GUI::new(world_size, Rc::clone(&tick_time)),
+            robots: Vec::new(),
+            gui: GUI::new(Rc::clone(&tick_time), settings),
+            tick_time,
+            export_on_exit: None,
+            grab_mouse,
+            plugins: Vec::new(),
+            notified_ticks: 0,
+        }
+    }
+
+    /// Registers another robot to be shown in the same view.
+    ///
+    /// Every robot is run by its own `Runner`, so pass a generator that produces the same world
+    /// as the one given to [`Visualizer::new`] (e.g. the same generator with the same seed).
+    pub fn add_robot(&mut self, name: &str, color: Color, robot: Box<dyn Runnable>, world_generator: impl Generator, channel: Rc<RefCell<Channel>>) {
+        self.robots.push(RobotEntry {
+            name: name.to_string(),
+            color,
+            source: RobotSource::Runner(RunnerWrapper::new(robot, world_generator, Rc::clone(&self.tick_time))),
+            channel,
+        });
+    }
+
+    /// Shows a robot running in another process, whose channel streams on `address`
+    /// (see [`Channel::enable_streaming`]).
+    ///
+    /// The viewer can be closed and opened again at any time without stopping the robot.
+    /// It fails if the robot doesn't send an update within 10 seconds, e.g. because it's paused or terminated.
+    pub fn new_viewer(address: &str) -> Result<Self, String> {
+        VisualizerBuilder::new().build_viewer(address)
+    }
+
+    /// Like [`Visualizer::new_viewer`], reading the stream from any source (e.g. a pipe or a recorded file)
+    pub fn new_viewer_from_reader(reader: impl Read + Send + 'static) -> Result<Self, String> {
+        VisualizerBuilder::new().build_viewer_from_reader(reader)
+    }
+
+    /// Waits for the snapshot at the start of a stream, returns the channel it was applied to
+    fn subscribe(reader: impl Read + Send + 'static) -> Result<(Rc<RefCell<Channel>>, Subscriber), String> {
+        let subscriber = Subscriber::new(reader);
+        let snapshot = subscriber.wait_for_snapshot()?;
+        let channel = Rc::new(RefCell::new(Channel::default()));
+        channel.borrow_mut().apply_stream_message(snapshot);
+
+        Ok((channel, subscriber))
+    }
+
+    fn add_remote_robot(&mut self, subscriber: Subscriber, channel: Rc<RefCell<Channel>>) {
+        self.robots.push(RobotEntry {
+            name: "Remote".to_string(),
+            color: WHITE,
+            source: RobotSource::Stream(subscriber),
             channel,
+        });
+    }
+
+    /// Runs two robots in lockstep on two copies of the same world and renders them side by side.
+    ///
+    /// The world is generated once and each robot gets its own copy, the camera is shared between the two views.
+    pub fn new_comparison(
+        world_generator: impl Generator,
+        left_robot: Box<dyn Runnable>,
+        left_channel: Rc<RefCell<Channel>>,
+        right_robot: Box<dyn Runnable>,
+        right_channel: Rc<RefCell<Channel>>,
+    ) -> Self {
+        let world_generator = ReplayGenerator::new(world_generator);
+        let mut visualizer = Self::new(left_robot, world_generator.clone(), left_channel);
+        visualizer.robots[0].name = "Left".to_string();
+        visualizer.add_robot("Right", ORANGE, right_robot, world_generator, right_channel);
+        visualizer.gui.set_split_screen(true);
+
+        visualizer
+    }
+
+    /// Registers a plugin, plugins are called in the order they were added
+    pub fn add_plugin(&mut self, plugin: Box<dyn OlympusPlugin>) {
+        self.plugins.push(plugin);
+    }
+
+    /// Calls `on_tick` of the plugins if the focused robot sent new game info
+    fn notify_plugins(&mut self, focused_robot: usize) {
+        let channel = self.robots[focused_robot].channel.borrow();
+        let data = channel.receive();
+        if data.ticks != self.notified_ticks {
+            self.notified_ticks = data.ticks;
+            for plugin in self.plugins.iter_mut() {
+                plugin.on_tick(data);
+            }
         }
     }
 
-    pub async fn start(&mut self) {
+    /// Exports the run data of every robot when the visualizer is closed,
+    /// see [`export::export_run`] for the files created with the given prefix.
+    ///
+    /// When more than one robot is shown, the name of the robot is appended to the prefix.
+    pub fn set_export_on_exit(&mut self, prefix: &str) {
+        self.export_on_exit = Some(prefix.to_string());
+    }
+
+    fn export_runs(&self, prefix: &str) -> Result<(), String> {
+        for robot in self.robots.iter() {
+            let prefix = if self.robots.len() == 1 {
+                prefix.to_string()
+            } else {
+                format!("{}_{}", prefix, robot.name.to_lowercase())
+            };
+            export::export_run(&robot.channel.borrow(), &prefix)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the visualizer until its window is closed.
+    ///
+    /// Returns the error of the export set with [`Visualizer::set_export_on_exit`], if it fails.
+    pub async fn start(&mut self) -> Result<(), String> {
         //set_pc_assets_folder("assets");
-        self.gui.ui.toggle_mouse_grab();
+        if self.grab_mouse {
+            self.gui.ui.toggle_mouse_grab();
+        }
 
         loop {
             self.gui.handle_input();
@@ -37,11 +197,53 @@ impl Visualizer {
                 break;
             }
 
-            self.runner.tick();
-            
-            self.gui.render(self.channel.borrow().receive());
-    
+            let focused_robot = self.gui.ui.selected_robot().min(self.robots.len() - 1);
+            for command in self.gui.ui.take_manual_commands() {
+                self.robots[focused_robot].channel.borrow_mut().push_command(command);
+            }
+
+            // All the robots advance together, so they can be compared tick by tick
+            let tick_due = self.robots.iter_mut()
+                .find_map(|robot| match &mut robot.source {
+                    RobotSource::Runner(runner) => Some(runner.is_tick_due()),
+                    RobotSource::Stream(_) => None,
+                })
+                .unwrap_or(false);
+            if self.gui.ui.should_tick(tick_due) {
+                for robot in self.robots.iter_mut() {
+                    if let RobotSource::Runner(runner) = &mut robot.source {
+                        runner.game_tick();
+                    }
+                }
+            }
+            // Remote robots don't wait for the viewer, their updates are shown as soon as they arrive
+            for robot in self.robots.iter() {
+                if let RobotSource::Stream(subscriber) = &robot.source {
+                    for message in subscriber.poll() {
+                        robot.channel.borrow_mut().apply_stream_message(message);
+                    }
+                }
+            }
+            self.notify_plugins(focused_robot);
+
+            let channels = self.robots.iter().map(|robot| robot.channel.borrow()).collect::<Vec<_>>();
+            let robots = self.robots.iter()
+                .zip(channels.iter())
+                .map(|(robot, channel)| RobotView {
+                    name: &robot.name,
+                    color: robot.color,
+                    data: channel.receive(),
+                    ground_truth: robot.get_ground_truth(),
+                })
+                .collect::<Vec<_>>();
+            self.gui.render(&robots, &mut self.plugins);
+
             next_frame().await
         }
+
+        match &self.export_on_exit {
+            Some(prefix) => self.export_runs(prefix),
+            None => Ok(()),
+        }
     }
-}
\ No newline at end of file
+}
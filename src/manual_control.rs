@@ -0,0 +1,138 @@
+use std::{cell::RefCell, rc::Rc};
+
+use robotics_lib::energy::Energy;
+use robotics_lib::event::events::Event;
+use robotics_lib::interface::{destroy, go, put, robot_map, teleport, Direction};
+use robotics_lib::runner::{backpack::BackPack, Runnable};
+use robotics_lib::world::{coordinates::Coordinate, tile::{Content, TileType}, World};
+
+use crate::channel::Channel;
+
+/// Action requested from the keyboard, executed on the next game tick
+pub(crate) enum ManualCommand {
+    ToggleManualMode,
+    Go(Direction),
+    Destroy(Direction),
+    /// Puts 1 unit of the content chosen in the GUI, `None` if the backpack was empty
+    Put(Direction, Option<Content>),
+    Teleport(Direction),
+}
+
+fn direction_name(direction: &Direction) -> &'static str {
+    match direction {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
+
+/// Wraps a robot so it can be driven from the keyboard.
+///
+/// While manual mode is off (toggle it with M) the wrapped robot plays as usual,
+/// while it's on the arrow keys move the robot and the other commands act in the last direction used.
+pub struct ManualControl<R: Runnable> {
+    robot: R,
+    channel: Rc<RefCell<Channel>>,
+}
+
+impl<R: Runnable> ManualControl<R> {
+    pub fn new(robot: R, channel: Rc<RefCell<Channel>>) -> Self {
+        channel.borrow_mut().enable_manual_control();
+
+        Self {
+            robot,
+            channel,
+        }
+    }
+
+    /// Closest activated teleport the robot knows of, in the given direction
+    fn find_teleport(&self, world: &World, direction: &Direction) -> Option<(usize, usize)> {
+        let map = robot_map(world)?;
+        let (row, col) = (self.get_coordinate().get_row(), self.get_coordinate().get_col());
+
+        map.iter()
+            .enumerate()
+            .flat_map(|(x, tiles)| tiles.iter().enumerate().map(move |(z, tile)| (x, z, tile)))
+            .filter(|(_, _, tile)| matches!(tile, Some(tile) if matches!(tile.tile_type, TileType::Teleport(true))))
+            .filter(|(x, z, _)| match direction {
+                Direction::Up => *x < row,
+                Direction::Down => *x > row,
+                Direction::Left => *z < col,
+                Direction::Right => *z > col,
+            })
+            .map(|(x, z, _)| (x, z))
+            .min_by_key(|(x, z)| x.abs_diff(row) + z.abs_diff(col))
+    }
+
+    fn execute(&mut self, command: ManualCommand, world: &mut World) -> String {
+        match command {
+            ManualCommand::ToggleManualMode => String::new(),
+            ManualCommand::Go(direction) => {
+                let name = direction_name(&direction);
+                match go(self, world, direction) {
+                    Ok((_, (row, col))) => format!("go {}: moved to ({}, {})", name, row, col),
+                    Err(error) => format!("go {}: {:?}", name, error),
+                }
+            }
+            ManualCommand::Destroy(direction) => {
+                let name = direction_name(&direction);
+                match destroy(self, world, direction) {
+                    Ok(amount) => format!("destroy {}: collected {}", name, amount),
+                    Err(error) => format!("destroy {}: {:?}", name, error),
+                }
+            }
+            ManualCommand::Put(direction, content) => {
+                let name = direction_name(&direction);
+                match content {
+                    Some(content) => {
+                        let content_name = content.to_string();
+                        match put(self, world, content, 1, direction) {
+                            Ok(amount) => format!("put {}: placed {} {}", name, amount, content_name),
+                            Err(error) => format!("put {}: {:?}", name, error),
+                        }
+                    }
+                    None => format!("put {}: the backpack is empty", name),
+                }
+            }
+            ManualCommand::Teleport(direction) => {
+                let name = direction_name(&direction);
+                match self.find_teleport(world, &direction) {
+                    Some(coordinates) => match teleport(self, world, coordinates) {
+                        Ok(_) => format!("teleport {}: teleported to ({}, {})", name, coordinates.0, coordinates.1),
+                        Err(error) => format!("teleport {}: {:?}", name, error),
+                    },
+                    None => format!("teleport {}: no known active teleport", name),
+                }
+            }
+        }
+    }
+}
+
+impl<R: Runnable> Runnable for ManualControl<R> {
+    fn process_tick(&mut self, world: &mut World) {
+        if !self.channel.borrow().is_manual_mode_on() {
+            self.robot.process_tick(world);
+            return;
+        }
+
+        let command = self.channel.borrow_mut().take_command();
+        if let Some(command) = command {
+            let result = self.execute(command, world);
+            self.channel.borrow_mut().log(result);
+        }
+
+        self.channel.borrow_mut().send_game_info(self, world);
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        self.robot.handle_event(event);
+    }
+
+    fn get_energy(&self) -> &Energy { self.robot.get_energy() }
+    fn get_energy_mut(&mut self) -> &mut Energy { self.robot.get_energy_mut() }
+    fn get_coordinate(&self) -> &Coordinate { self.robot.get_coordinate() }
+    fn get_coordinate_mut(&mut self) -> &mut Coordinate { self.robot.get_coordinate_mut() }
+    fn get_backpack(&self) -> &BackPack { self.robot.get_backpack() }
+    fn get_backpack_mut(&mut self) -> &mut BackPack { self.robot.get_backpack_mut() }
+}
@@ -0,0 +1,57 @@
+use macroquad::prelude::*;
+use macroquad::ui::Ui;
+
+use crate::channel::ChannelData;
+
+/// What a plugin needs to draw in the 3D view, the camera of the view is already set
+pub struct RenderContext<'a> {
+    pub camera: &'a Camera3D,
+    /// Part of the screen the view is drawn on, in split screen each view gets its own context
+    pub viewport: Rect,
+    /// Data of the robot shown in the view
+    pub data: &'a ChannelData,
+}
+
+impl<'a> RenderContext<'a> {
+    /// Center of the top face of a tile, where the contents and the robot are drawn.
+    ///
+    /// Unexplored tiles are considered at elevation 1, like the explored tiles at elevation 0.
+    pub fn tile_position(&self, (row, col): (usize, usize)) -> Vec3 {
+        let elevation = self.data.explored_world_map
+            .get(row)
+            .and_then(|tiles| tiles.get(col))
+            .and_then(|tile| tile.as_ref())
+            .map_or(1, |tile| tile.elevation.max(1));
+
+        vec3(row as f32 + 0.5, elevation as f32, col as f32 + 0.5)
+    }
+}
+
+/// Extension registered with [`Visualizer::add_plugin`](crate::Visualizer::add_plugin)
+/// to draw custom overlays and panels without changing the visualizer.
+///
+/// Every method does nothing by default, implement only the ones you need.
+///
+/// ```ignore
+/// struct TargetMarker {
+///     target: Rc<RefCell<Option<(usize, usize)>>>, // Shared with the robot
+/// }
+///
+/// impl OlympusPlugin for TargetMarker {
+///     fn draw_3d(&self, context: &RenderContext) {
+///         if let Some(target) = *self.target.borrow() {
+///             draw_cube_wires(context.tile_position(target) + vec3(0.0, 0.5, 0.0), vec3(1.0, 1.0, 1.0), RED);
+///         }
+///     }
+/// }
+/// ```
+pub trait OlympusPlugin {
+    /// Called once for every game tick of the focused robot, after the robot sent its game info
+    fn on_tick(&mut self, _data: &ChannelData) {}
+
+    /// Called every frame for every 3D view, after the world is drawn
+    fn draw_3d(&self, _context: &RenderContext) {}
+
+    /// Called every frame while the HUD is shown, the plugin can open its own windows on `ui`
+    fn draw_ui(&mut self, _ui: &mut Ui) {}
+}
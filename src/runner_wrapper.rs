@@ -1,31 +1,81 @@
 use std::{cell::RefCell, rc::Rc};
 
 use macroquad::time::get_time;
-use robotics_lib::{runner::{Runnable, Runner}, world::world_generator::Generator};
+use robotics_lib::{runner::{Runnable, Runner}, world::{tile::Tile, world_generator::{Generator, World}}};
+
+/// Generator that keeps a copy of the generated map, so it can be shown next to what the robot explored
+struct CapturingGenerator<G: Generator> {
+    world_generator: G,
+    world_map: Vec<Vec<Tile>>,
+}
+
+impl<G: Generator> Generator for CapturingGenerator<G> {
+    fn gen(&mut self) -> World {
+        let world = self.world_generator.gen();
+        self.world_map = world.0.clone();
+        world
+    }
+}
+
+/// Generator returning a copy of a world generated once, so several robots can start from the same world
+/// even if the original generator uses a random seed
+#[derive(Clone)]
+pub(super) struct ReplayGenerator {
+    world: World,
+}
+
+impl ReplayGenerator {
+    pub(super) fn new(mut world_generator: impl Generator) -> Self {
+        Self {
+            world: world_generator.gen(),
+        }
+    }
+}
+
+impl Generator for ReplayGenerator {
+    fn gen(&mut self) -> World {
+        self.world.clone()
+    }
+}
 
 pub(super) struct RunnerWrapper {
     runner: Runner,
+    ground_truth: Vec<Vec<Tile>>,
     last_time: f64,
     current_time: f64,
     tick_time: Rc<RefCell<f32>>
 }
 
 impl RunnerWrapper {
-    pub(super) fn new(robot: Box<dyn Runnable>, mut world_generator: impl Generator, tick_time: Rc<RefCell<f32>>) -> Self {
+    pub(super) fn new(robot: Box<dyn Runnable>, world_generator: impl Generator, tick_time: Rc<RefCell<f32>>) -> Self {
+        let mut world_generator = CapturingGenerator {
+            world_generator,
+            world_map: Vec::new(),
+        };
+        let runner = Runner::new(robot, &mut world_generator).expect("Error creating runner");
+
         Self {
-            runner: Runner::new(robot, &mut world_generator).expect("Error creating runner"),
+            runner,
+            ground_truth: world_generator.world_map,
             last_time: get_time(),
             current_time: get_time(),
             tick_time
         }
     }
 
-    pub(super) fn tick(&mut self) {
+    /// Full world map as it was generated
+    pub(super) fn get_ground_truth(&self) -> &Vec<Vec<Tile>> {
+        &self.ground_truth
+    }
+
+    pub(super) fn is_tick_due(&mut self) -> bool {
         self.current_time = get_time();
         
-        if (self.current_time - self.last_time) > *self.tick_time.borrow() as f64 {
-            self.runner.game_tick().expect("Error during game tick");
-            self.last_time = self.current_time;
-        }
+        (self.current_time - self.last_time) > *self.tick_time.borrow() as f64
+    }
+
+    pub(super) fn game_tick(&mut self) {
+        self.runner.game_tick().expect("Error during game tick");
+        self.last_time = self.current_time;
     }
 }
\ No newline at end of file
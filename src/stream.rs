@@ -0,0 +1,523 @@
+//! Publishing of the visualization state on a local TCP socket.
+//!
+//! Enable it with [`Channel::enable_streaming`](crate::channel::Channel::enable_streaming),
+//! any number of clients can then connect to the given address and disconnect at any time.
+//!
+//! # Message format
+//!
+//! Every message is a JSON object on its own line (newline delimited JSON),
+//! the `type` field tells which one of the [`StreamMessage`] variants it is:
+//!
+//! - `snapshot`: sent once to every client right after it connects, it contains the whole explored map,
+//!   the robot state, the weather and the event log.
+//! - `map_delta`: the explored tiles that were discovered or changed since the previous update.
+//! - `robot`: the robot state, sent every time the robot sends its game info.
+//! - `weather`: time of day and weather condition, sent every time the robot sends its weather info.
+//! - `event`: a new line of the event log.
+//!
+//! ```text
+//! {"type":"snapshot","rows":200,"cols":200,"tiles":[...],"robot":{...},"weather":{...},"events":[...]}
+//! {"type":"map_delta","tiles":[{"row":3,"col":4,"tile_type":"Grass","teleport_active":null,"elevation":2,"content":"Tree","content_amount":3,"content_capacity":null}]}
+//! {"type":"robot","row":3,"col":5,"energy":980,"score":12.5,"backpack_size":20,"backpack":{"Tree":3},"discoverable_tiles":1200,"custom":{"goal":"explore"},"backpack_changes":{"Tree":1},"energy_consumed":5,"energy_recharged":0,"terminated":false}
+//! {"type":"weather","time_of_day":"13:30","day_time":"Afternoon","weather_condition":"Rainy"}
+//! {"type":"event","text":"go up: moved to (2, 5)"}
+//! ```
+//!
+//! Tile types, contents and weather conditions use the names of the `robotics_lib` variants.
+//! `content_capacity` is set only for the contents holding a range (`Bin`, `Crate` and `Bank`),
+//! in that case `content_amount` is the start of the range and `content_capacity` is the end.
+//!
+//! See `examples/stream_client.rs` for a client that prints the messages it receives.
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use robotics_lib::world::tile::{Tile, TileType};
+use serde::{Deserialize, Serialize};
+
+use crate::channel::{ChannelData, CustomValue};
+use crate::world_info::{content_amount, content_capacity, content_from_name, content_name, day_time_name, tile_type_from_name, tile_type_name, weather_name};
+
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Lines waiting to be written to a client before it's considered stalled and dropped
+const CLIENT_QUEUE_SIZE: usize = 256;
+/// The publisher sends the snapshot at the next update of the robot, a paused or terminated robot never sends it
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StreamTile {
+    pub row: usize,
+    pub col: usize,
+    pub tile_type: String,
+    pub teleport_active: Option<bool>,
+    pub elevation: usize,
+    pub content: String,
+    pub content_amount: Option<usize>,
+    pub content_capacity: Option<usize>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RobotState {
+    pub row: usize,
+    pub col: usize,
+    pub energy: usize,
+    pub score: f32,
+    pub backpack_size: usize,
+    pub backpack: BTreeMap<String, usize>,
+    pub discoverable_tiles: usize,
+    /// Values sent with `Channel::send_custom`, numbers, strings or booleans
+    #[serde(default)]
+    pub custom: BTreeMap<String, CustomValue>,
+    /// Amounts added to or removed from the backpack in the last tick
+    #[serde(default)]
+    pub backpack_changes: BTreeMap<String, isize>,
+    /// Energy consumed and recharged in the last tick
+    #[serde(default)]
+    pub energy_consumed: usize,
+    #[serde(default)]
+    pub energy_recharged: usize,
+    #[serde(default)]
+    pub terminated: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WeatherState {
+    pub time_of_day: String,
+    pub day_time: String,
+    pub weather_condition: String,
+}
+
+/// A line of the stream, see the [module documentation](self) for the format
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamMessage {
+    Snapshot {
+        rows: usize,
+        cols: usize,
+        tiles: Vec<StreamTile>,
+        robot: RobotState,
+        weather: WeatherState,
+        events: Vec<String>,
+    },
+    MapDelta {
+        tiles: Vec<StreamTile>,
+    },
+    Robot(RobotState),
+    Weather(WeatherState),
+    Event {
+        text: String,
+    },
+}
+
+impl StreamTile {
+    /// `None` if the names of the tile type or of the content are unknown
+    pub(crate) fn to_tile(&self) -> Option<Tile> {
+        Some(Tile {
+            tile_type: tile_type_from_name(&self.tile_type, self.teleport_active)?,
+            content: content_from_name(&self.content, self.content_amount.unwrap_or(0), self.content_capacity)?,
+            elevation: self.elevation,
+        })
+    }
+}
+
+fn stream_tile(row: usize, col: usize, tile: &Tile) -> StreamTile {
+    StreamTile {
+        row,
+        col,
+        tile_type: tile_type_name(&tile.tile_type).to_string(),
+        teleport_active: match tile.tile_type {
+            TileType::Teleport(active) => Some(active),
+            _ => None,
+        },
+        elevation: tile.elevation,
+        content: content_name(&tile.content).to_string(),
+        content_amount: content_amount(&tile.content),
+        content_capacity: content_capacity(&tile.content),
+    }
+}
+
+fn robot_state(data: &ChannelData) -> RobotState {
+    RobotState {
+        row: data.robot_coordinates.0,
+        col: data.robot_coordinates.1,
+        energy: data.robot_energy,
+        score: data.robot_score,
+        backpack_size: data.robot_backpack_size,
+        backpack: data.robot_backpack_contents.iter()
+            .map(|(content, amount)| (content_name(content).to_string(), *amount))
+            .collect(),
+        discoverable_tiles: data.discoverable_tiles,
+        custom: data.custom_values.clone(),
+        backpack_changes: data.backpack_changes.clone(),
+        energy_consumed: data.energy_consumed,
+        energy_recharged: data.energy_recharged,
+        terminated: data.terminated,
+    }
+}
+
+fn weather_state(data: &ChannelData) -> WeatherState {
+    WeatherState {
+        time_of_day: data.time_of_day_string.clone(),
+        day_time: day_time_name(data.time_of_day).to_string(),
+        weather_condition: weather_name(data.weather_condition).to_string(),
+    }
+}
+
+/// Connected client, the socket is written by its own thread so a slow client never blocks the rendering
+struct Client {
+    lines: SyncSender<Arc<[u8]>>,
+}
+
+impl Client {
+    fn new(mut stream: TcpStream) -> Option<Self> {
+        stream.set_nonblocking(false).ok()?;
+        // A stalled client is dropped when its queue is full, the timeout only ends its thread
+        stream.set_write_timeout(Some(WRITE_TIMEOUT)).ok()?;
+
+        let (lines, queue) = mpsc::sync_channel::<Arc<[u8]>>(CLIENT_QUEUE_SIZE);
+        thread::spawn(move || {
+            for line in queue {
+                if stream.write_all(&line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Some(Self {
+            lines,
+        })
+    }
+
+    /// False if the client disconnected or can't keep up
+    fn send(&self, line: Arc<[u8]>) -> bool {
+        self.lines.try_send(line).is_ok()
+    }
+}
+
+/// Sends the updates of a channel to the connected clients
+pub(crate) struct Publisher {
+    listener: TcpListener,
+    clients: Vec<Client>,
+    /// Explored map as the clients know it, used only while there are clients
+    published_map: Vec<Vec<Option<Tile>>>,
+}
+
+impl Publisher {
+    pub(crate) fn bind(address: &str) -> Result<Self, String> {
+        let listener = TcpListener::bind(address).map_err(|error| format!("Error listening on {}: {}", address, error))?;
+        listener.set_nonblocking(true).map_err(|error| format!("Error listening on {}: {}", address, error))?;
+
+        Ok(Self {
+            listener,
+            clients: Vec::new(),
+            published_map: Vec::new(),
+        })
+    }
+
+    /// Address the clients connect to, with the actual port when bound to port 0
+    pub(crate) fn local_addr(&self) -> Result<SocketAddr, String> {
+        self.listener.local_addr().map_err(|error| format!("Error reading the streaming address: {}", error))
+    }
+
+    fn encode(message: &StreamMessage) -> Option<Arc<[u8]>> {
+        let mut line = serde_json::to_vec(message).ok()?;
+        line.push(b'\n');
+        Some(line.into())
+    }
+
+    /// Clients that can't keep up or disconnected are dropped
+    fn broadcast(&mut self, message: &StreamMessage) {
+        if self.clients.is_empty() {
+            return;
+        }
+
+        if let Some(line) = Self::encode(message) {
+            self.clients.retain(|client| client.send(Arc::clone(&line)));
+        }
+    }
+
+    /// Every new client gets a snapshot, so it doesn't need to wait for the whole map to be discovered again
+    fn accept_clients(&mut self, data: &ChannelData) {
+        if data.ticks == 0 {
+            return; // The size of the map is known only after the first game info
+        }
+
+        while let Ok((stream, _)) = self.listener.accept() {
+            let snapshot = StreamMessage::Snapshot {
+                rows: data.explored_world_map.len(),
+                cols: data.explored_world_map.first().map_or(0, |row| row.len()),
+                tiles: data.explored_world_map.iter()
+                    .enumerate()
+                    .flat_map(|(row, tiles)| tiles.iter().enumerate().map(move |(col, tile)| (row, col, tile)))
+                    .filter_map(|(row, col, tile)| tile.as_ref().map(|tile| stream_tile(row, col, tile)))
+                    .collect(),
+                robot: robot_state(data),
+                weather: weather_state(data),
+                events: data.event_log.iter().cloned().collect(),
+            };
+
+            let client = Client::new(stream);
+            if let (Some(client), Some(line)) = (client, Self::encode(&snapshot)) {
+                if client.send(line) {
+                    // The next delta starts from the snapshot
+                    self.published_map = data.explored_world_map.clone();
+                    self.clients.push(client);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn publish_game_info(&mut self, data: &ChannelData) {
+        // Without clients there is nothing to diff against, the next client gets a snapshot anyway
+        if !self.clients.is_empty() {
+            let tiles = data.explored_world_map.iter()
+                .enumerate()
+                .flat_map(|(row, tiles)| tiles.iter().enumerate().map(move |(col, tile)| (row, col, tile)))
+                .filter_map(|(row, col, tile)| {
+                    let published = self.published_map.get(row).and_then(|tiles| tiles.get(col)).and_then(|tile| tile.as_ref());
+                    match tile {
+                        Some(tile) if published != Some(tile) => Some(stream_tile(row, col, tile)),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>();
+            self.published_map = data.explored_world_map.clone();
+
+            if !tiles.is_empty() {
+                self.broadcast(&StreamMessage::MapDelta { tiles });
+            }
+            self.broadcast(&StreamMessage::Robot(robot_state(data)));
+            if self.clients.is_empty() {
+                self.published_map = Vec::new();
+            }
+        }
+        self.accept_clients(data);
+    }
+
+    pub(crate) fn publish_weather_info(&mut self, data: &ChannelData) {
+        self.broadcast(&StreamMessage::Weather(weather_state(data)));
+        self.accept_clients(data);
+    }
+
+    pub(crate) fn publish_event(&mut self, data: &ChannelData, text: &str) {
+        self.broadcast(&StreamMessage::Event { text: text.to_string() });
+        self.accept_clients(data);
+    }
+}
+
+/// Reads a stream in a background thread, so a slow or silent publisher never blocks the rendering
+pub(crate) struct Subscriber {
+    messages: Receiver<StreamMessage>,
+}
+
+impl Subscriber {
+    pub(crate) fn new(reader: impl Read + Send + 'static) -> Self {
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                // Lines that aren't messages are skipped, a newer publisher may send messages this version doesn't know
+                if let Ok(message) = serde_json::from_str::<StreamMessage>(&line) {
+                    if sender.send(message).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Self {
+            messages,
+        }
+    }
+
+    /// Blocks until the snapshot sent on connection arrives, the messages before it are dropped.
+    ///
+    /// Fails if the snapshot doesn't arrive within 10 seconds.
+    pub(crate) fn wait_for_snapshot(&self) -> Result<StreamMessage, String> {
+        let deadline = Instant::now() + SNAPSHOT_TIMEOUT;
+        loop {
+            match self.messages.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(message @ StreamMessage::Snapshot { .. }) => return Ok(message),
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => return Err(format!(
+                    "No snapshot received in {} seconds, the robot sends it at its next update so it must be running",
+                    SNAPSHOT_TIMEOUT.as_secs()
+                )),
+                Err(RecvTimeoutError::Disconnected) => return Err("The stream ended before sending a snapshot".to_string()),
+            }
+        }
+    }
+
+    /// Messages received since the last call
+    pub(crate) fn poll(&self) -> Vec<StreamMessage> {
+        self.messages.try_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpStream;
+    use std::time::Duration;
+
+    use robotics_lib::world::tile::{Content, TileType};
+
+    use super::{stream_tile, Publisher, RobotState, StreamMessage, StreamTile, WeatherState};
+    use crate::channel::{ChannelData, CustomValue, TickMetrics};
+    use crate::test_fixtures::{explored, metrics, tile};
+
+    /// What `Channel::send_game_info` records, the publisher accepts clients only after the first tick
+    fn record_tick(data: &mut ChannelData) {
+        data.history.push_back(TickMetrics {
+            time_of_day: data.time_of_day_string.clone(),
+            robot_row: data.robot_coordinates.0,
+            robot_col: data.robot_coordinates.1,
+            robot_energy: data.robot_energy,
+            robot_score: data.robot_score,
+            ..metrics(data.ticks)
+        });
+        data.ticks += 1;
+    }
+
+    fn read_message(lines: &mut impl Iterator<Item = std::io::Result<String>>) -> StreamMessage {
+        let line = lines.next().expect("The stream ended").expect("Error reading the stream");
+        serde_json::from_str(&line).expect("Invalid message")
+    }
+
+    #[test]
+    fn loopback_client_receives_snapshot_then_updates() {
+        let mut publisher = Publisher::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(publisher.local_addr().unwrap()).unwrap();
+        client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut lines = BufReader::new(client).lines();
+
+        let mut data = ChannelData {
+            explored_world_map: vec![vec![None; 3]; 2],
+            ..Default::default()
+        };
+        data.explored_world_map[0][0] = explored(TileType::Grass, Content::None);
+        record_tick(&mut data);
+        publisher.publish_game_info(&data);
+
+        data.explored_world_map[1][2] = explored(TileType::Sand, Content::Rock(2));
+        data.robot_coordinates = (1, 2);
+        record_tick(&mut data);
+        publisher.publish_game_info(&data);
+        publisher.publish_event(&data, "go right: moved to (1, 2)");
+
+        match read_message(&mut lines) {
+            StreamMessage::Snapshot { rows, cols, tiles, .. } => {
+                assert_eq!((rows, cols), (2, 3));
+                assert_eq!(tiles.iter().map(|tile| (tile.row, tile.col)).collect::<Vec<_>>(), vec![(0, 0)]);
+            }
+            _ => panic!("Expected a snapshot"),
+        }
+        match read_message(&mut lines) {
+            StreamMessage::MapDelta { tiles } => {
+                assert_eq!(tiles.len(), 1);
+                assert_eq!((tiles[0].row, tiles[0].col), (1, 2));
+                assert_eq!(tiles[0].tile_type, "Sand");
+                assert_eq!(tiles[0].content, "Rock");
+                assert_eq!(tiles[0].content_amount, Some(2));
+            }
+            _ => panic!("Expected a map delta"),
+        }
+        match read_message(&mut lines) {
+            StreamMessage::Robot(robot) => assert_eq!((robot.row, robot.col), (1, 2)),
+            _ => panic!("Expected the robot state"),
+        }
+        match read_message(&mut lines) {
+            StreamMessage::Event { text } => assert_eq!(text, "go right: moved to (1, 2)"),
+            _ => panic!("Expected an event"),
+        }
+    }
+
+    #[test]
+    fn tiles_survive_the_round_trip() {
+        let tiles = [
+            tile(TileType::Grass, Content::Rock(2)),
+            tile(TileType::Teleport(true), Content::None),
+            tile(TileType::Teleport(false), Content::None),
+            tile(TileType::Street, Content::Bin(3..10)),
+            tile(TileType::Lava, Content::Fire),
+        ];
+
+        for (col, tile) in tiles.iter().enumerate() {
+            let json = serde_json::to_string(&stream_tile(4, col, tile)).unwrap();
+            let restored = serde_json::from_str::<StreamTile>(&json).unwrap()
+                .to_tile()
+                .expect("Unknown tile type or content");
+            assert_eq!(serde_json::to_string(&stream_tile(4, col, &restored)).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn messages_survive_the_round_trip() {
+        let robot = RobotState {
+            row: 1,
+            col: 2,
+            energy: 500,
+            score: 12.5,
+            backpack_size: 20,
+            backpack: [("Rock".to_string(), 3)].into_iter().collect(),
+            discoverable_tiles: 100,
+            custom: [
+                ("goal".to_string(), CustomValue::Text("coins".to_string())),
+                ("stuck".to_string(), CustomValue::Bool(false)),
+                ("targets".to_string(), CustomValue::Number(4.0)),
+            ].into_iter().collect(),
+            backpack_changes: [("Rock".to_string(), -2)].into_iter().collect(),
+            energy_consumed: 30,
+            energy_recharged: 10,
+            terminated: true,
+        };
+        let messages = [
+            StreamMessage::Snapshot {
+                rows: 2,
+                cols: 3,
+                tiles: vec![stream_tile(0, 0, &tile(TileType::Grass, Content::Tree(1)))],
+                robot: robot.clone(),
+                weather: WeatherState {
+                    time_of_day: "08:30".to_string(),
+                    day_time: "Morning".to_string(),
+                    weather_condition: "Rainy".to_string(),
+                },
+                events: vec!["Started".to_string()],
+            },
+            StreamMessage::MapDelta { tiles: Vec::new() },
+            StreamMessage::Robot(robot),
+            StreamMessage::Event { text: "go right: moved to (1, 2)".to_string() },
+        ];
+
+        for message in messages.iter() {
+            let json = serde_json::to_string(message).unwrap();
+            let restored = serde_json::from_str::<StreamMessage>(&json).unwrap();
+            assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+        }
+        assert_eq!(
+            serde_json::to_string(&messages[3]).unwrap(),
+            r#"{"type":"event","text":"go right: moved to (1, 2)"}"#
+        );
+    }
+
+    #[test]
+    fn robot_messages_without_the_optional_fields_are_accepted() {
+        let line = r#"{"type":"robot","row":1,"col":2,"energy":500,"score":0.0,"backpack_size":20,"backpack":{},"discoverable_tiles":100}"#;
+
+        match serde_json::from_str::<StreamMessage>(line).unwrap() {
+            StreamMessage::Robot(robot) => {
+                assert_eq!((robot.row, robot.col, robot.energy), (1, 2, 500));
+                assert!(robot.custom.is_empty() && robot.backpack_changes.is_empty());
+                assert_eq!((robot.energy_consumed, robot.energy_recharged, robot.terminated), (0, 0, false));
+            }
+            _ => panic!("Expected the robot state"),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Values shared by the unit tests of the modules
+
+use robotics_lib::world::tile::{Content, Tile, TileType};
+
+use crate::channel::TickMetrics;
+
+/// Tile at elevation 0, change the elevation with `Tile { elevation, ..tile(..) }`
+pub(crate) fn tile(tile_type: TileType, content: Content) -> Tile {
+    Tile {
+        tile_type,
+        content,
+        elevation: 0,
+    }
+}
+
+/// Explored tile at elevation 0
+pub(crate) fn explored(tile_type: TileType, content: Content) -> Option<Tile> {
+    Some(tile(tile_type, content))
+}
+
+/// Metrics of a tick with every value at zero, set the ones a test needs with `TickMetrics { .., ..metrics(tick) }`
+pub(crate) fn metrics(tick: usize) -> TickMetrics {
+    TickMetrics {
+        tick,
+        time_of_day: "08:00".to_string(),
+        weather_condition: "Sunny",
+        robot_row: 0,
+        robot_col: 0,
+        robot_energy: 0,
+        robot_score: 0.0,
+        explored_tiles: 0,
+        backpack_items: 0,
+        energy_consumed: 0,
+        energy_recharged: 0,
+        custom: Default::default(),
+    }
+}
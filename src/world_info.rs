@@ -0,0 +1,217 @@
+use robotics_lib::world::environmental_conditions::{DayTime, WeatherType};
+use robotics_lib::world::tile::{Content, TileType};
+
+/// One content of each kind, in the order of the `robotics_lib` variants
+pub(crate) fn contents() -> [Content; 16] {
+    [
+        Content::Rock(0),
+        Content::Tree(0),
+        Content::Garbage(0),
+        Content::Fire,
+        Content::Coin(0),
+        Content::Bin(0..0),
+        Content::Crate(0..0),
+        Content::Bank(0..0),
+        Content::Water(0),
+        Content::Market(0),
+        Content::Fish(0),
+        Content::Building,
+        Content::Bush(0),
+        Content::JollyBlock(0),
+        Content::Scarecrow,
+        Content::None,
+    ]
+}
+
+/// One tile type of each kind, in the order of the `robotics_lib` variants
+pub(crate) fn tile_types() -> [TileType; 11] {
+    [
+        TileType::DeepWater,
+        TileType::ShallowWater,
+        TileType::Sand,
+        TileType::Grass,
+        TileType::Street,
+        TileType::Hill,
+        TileType::Mountain,
+        TileType::Snow,
+        TileType::Lava,
+        TileType::Teleport(false),
+        TileType::Wall,
+    ]
+}
+
+pub(crate) fn content_name(content: &Content) -> &'static str {
+    match content {
+        Content::Rock(_) => "Rock",
+        Content::Tree(_) => "Tree",
+        Content::Garbage(_) => "Garbage",
+        Content::Fire => "Fire",
+        Content::Coin(_) => "Coin",
+        Content::Bin(_) => "Bin",
+        Content::Crate(_) => "Crate",
+        Content::Bank(_) => "Bank",
+        Content::Water(_) => "Water",
+        Content::Market(_) => "Market",
+        Content::Fish(_) => "Fish",
+        Content::Building => "Building",
+        Content::Bush(_) => "Bush",
+        Content::JollyBlock(_) => "JollyBlock",
+        Content::Scarecrow => "Scarecrow",
+        Content::None => "None",
+    }
+}
+
+pub(crate) fn tile_type_name(tile_type: &TileType) -> &'static str {
+    match tile_type {
+        TileType::DeepWater => "DeepWater",
+        TileType::ShallowWater => "ShallowWater",
+        TileType::Sand => "Sand",
+        TileType::Grass => "Grass",
+        TileType::Street => "Street",
+        TileType::Hill => "Hill",
+        TileType::Mountain => "Mountain",
+        TileType::Snow => "Snow",
+        TileType::Lava => "Lava",
+        TileType::Teleport(_) => "Teleport",
+        TileType::Wall => "Wall",
+    }
+}
+
+pub(crate) fn weather_name(weather_condition: WeatherType) -> &'static str {
+    match weather_condition {
+        WeatherType::Sunny => "Sunny",
+        WeatherType::Rainy => "Rainy",
+        WeatherType::Foggy => "Foggy",
+        WeatherType::TropicalMonsoon => "TropicalMonsoon",
+        WeatherType::TrentinoSnow => "TrentinoSnow",
+    }
+}
+
+/// Amount carried by the contents that have one
+pub(crate) fn content_amount(content: &Content) -> Option<usize> {
+    match content {
+        Content::Rock(amount)
+        | Content::Tree(amount)
+        | Content::Garbage(amount)
+        | Content::Coin(amount)
+        | Content::Water(amount)
+        | Content::Market(amount)
+        | Content::Fish(amount)
+        | Content::Bush(amount)
+        | Content::JollyBlock(amount) => Some(*amount),
+        Content::Bin(range) | Content::Crate(range) | Content::Bank(range) => Some(range.start),
+        _ => None,
+    }
+}
+
+/// Capacity of the contents holding a range, the end of the range
+pub(crate) fn content_capacity(content: &Content) -> Option<usize> {
+    match content {
+        Content::Bin(range) | Content::Crate(range) | Content::Bank(range) => Some(range.end),
+        _ => None,
+    }
+}
+
+pub(crate) fn day_time_name(day_time: DayTime) -> &'static str {
+    match day_time {
+        DayTime::Morning => "Morning",
+        DayTime::Afternoon => "Afternoon",
+        DayTime::Night => "Night",
+    }
+}
+
+pub(crate) fn tile_type_from_name(name: &str, teleport_active: Option<bool>) -> Option<TileType> {
+    match name {
+        "DeepWater" => Some(TileType::DeepWater),
+        "ShallowWater" => Some(TileType::ShallowWater),
+        "Sand" => Some(TileType::Sand),
+        "Grass" => Some(TileType::Grass),
+        "Street" => Some(TileType::Street),
+        "Hill" => Some(TileType::Hill),
+        "Mountain" => Some(TileType::Mountain),
+        "Snow" => Some(TileType::Snow),
+        "Lava" => Some(TileType::Lava),
+        "Teleport" => Some(TileType::Teleport(teleport_active.unwrap_or(false))),
+        "Wall" => Some(TileType::Wall),
+        _ => None,
+    }
+}
+
+/// Inverse of [`content_name`], for the contents holding a range `amount` is the start and `capacity` the end
+pub(crate) fn content_from_name(name: &str, amount: usize, capacity: Option<usize>) -> Option<Content> {
+    let range = amount..capacity.unwrap_or(amount);
+    match name {
+        "Rock" => Some(Content::Rock(amount)),
+        "Tree" => Some(Content::Tree(amount)),
+        "Garbage" => Some(Content::Garbage(amount)),
+        "Fire" => Some(Content::Fire),
+        "Coin" => Some(Content::Coin(amount)),
+        "Bin" => Some(Content::Bin(range)),
+        "Crate" => Some(Content::Crate(range)),
+        "Bank" => Some(Content::Bank(range)),
+        "Water" => Some(Content::Water(amount)),
+        "Market" => Some(Content::Market(amount)),
+        "Fish" => Some(Content::Fish(amount)),
+        "Building" => Some(Content::Building),
+        "Bush" => Some(Content::Bush(amount)),
+        "JollyBlock" => Some(Content::JollyBlock(amount)),
+        "Scarecrow" => Some(Content::Scarecrow),
+        "None" => Some(Content::None),
+        _ => None,
+    }
+}
+
+pub(crate) fn weather_from_name(name: &str) -> Option<WeatherType> {
+    match name {
+        "Sunny" => Some(WeatherType::Sunny),
+        "Rainy" => Some(WeatherType::Rainy),
+        "Foggy" => Some(WeatherType::Foggy),
+        "TropicalMonsoon" => Some(WeatherType::TropicalMonsoon),
+        "TrentinoSnow" => Some(WeatherType::TrentinoSnow),
+        _ => None,
+    }
+}
+
+pub(crate) fn day_time_from_name(name: &str) -> Option<DayTime> {
+    match name {
+        "Morning" => Some(DayTime::Morning),
+        "Afternoon" => Some(DayTime::Afternoon),
+        "Night" => Some(DayTime::Night),
+        _ => None,
+    }
+}
+
+const CONTENT_REFERENCE_AMOUNT: f32 = 20.0;
+const CONTENT_MAX_STACK: usize = 4;
+/// Width and height of the gauge drawn next to the containers, at its full height
+pub(crate) const FILL_GAUGE_SIZE: (f32, f32) = (0.08, 0.5);
+/// Distance of the gauge from the center of the tile, along the rows
+pub(crate) const FILL_GAUGE_OFFSET: f32 = 0.35;
+
+/// How full a container is, from 0.0 to 1.0
+pub(crate) fn content_fill(content: &Content) -> Option<f32> {
+    match content {
+        Content::Bin(range) | Content::Crate(range) | Content::Bank(range) => {
+            Some((range.start as f32 / range.end.max(1) as f32).clamp(0.0, 1.0))
+        }
+        Content::Market(amount) => Some((*amount as f32 / CONTENT_REFERENCE_AMOUNT).clamp(0.0, 1.0)),
+        _ => None,
+    }
+}
+
+/// Stacked cubes are smaller, so the stacks don't look like walls
+pub(crate) fn content_cube_size(stack_height: usize) -> f32 {
+    if stack_height > 1 { 0.4 } else { 0.5 }
+}
+
+/// Number of cubes stacked on a tile, proportional to the content amount
+pub(crate) fn content_stack_height(content: &Content) -> usize {
+    match (content, content_amount(content)) {
+        (Content::Market(_) | Content::Bin(_) | Content::Crate(_) | Content::Bank(_), _) => 1, // These show their amount with the gauge
+        (_, Some(amount)) => {
+            let ratio = amount as f32 / CONTENT_REFERENCE_AMOUNT;
+            ((ratio * CONTENT_MAX_STACK as f32).ceil() as usize).clamp(1, CONTENT_MAX_STACK)
+        }
+        (_, None) => 1,
+    }
+}